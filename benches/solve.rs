@@ -0,0 +1,119 @@
+//! Compares solving with a freshly constructed formula against reusing a cached one, the
+//! pairwise encoding against the sequential one, and checking minimality with a solver per clue
+//! against a single solver with the clues as assumptions, and solving the neighbors of a puzzle
+//! from scratch against warm-starting from its solution. Run with `cargo bench --features bench`.
+use std::time::{Duration, Instant};
+
+use sudosat::{Encoding, Grid, SolveOptions};
+
+const ITERATIONS: usize = 100;
+
+/// A minimal puzzle with 25 clues.
+const MINIMAL: &str =
+    "  79   4  38 41   2         7 8    4    2   5  659 3    4 7 6     6   29    85   ";
+
+/// Checks minimality the way [`Grid::is_minimal`] used to, with a new solver for every clue.
+fn is_minimal_rebuilt(grid: &Grid) -> bool {
+    grid.has_unique_solution()
+        && grid.filled_cells().all(|(x, y, _)| {
+            let mut reduced = grid.clone();
+            reduced[(x, y)] = None;
+            !reduced.has_unique_solution()
+        })
+}
+
+fn main() {
+    let grid: Grid =
+        "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  "
+            .parse()
+            .unwrap();
+
+    let mut fresh_formula = Duration::ZERO;
+    let mut fresh_solve = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        let (_, stats) = grid.solve_with(&SolveOptions::default()).unwrap();
+        fresh_formula += stats.formula_time;
+        fresh_solve += stats.solve_time;
+    }
+
+    let stats = grid.solve_repeated(ITERATIONS).unwrap();
+    let cached_formula = stats.iter().map(|s| s.formula_time).sum::<Duration>();
+    let cached_solve = stats.iter().map(|s| s.solve_time).sum::<Duration>();
+
+    let per_iteration = |d: Duration| d / ITERATIONS as u32;
+    println!(
+        "fresh:  formula {:?}, solve {:?} per iteration",
+        per_iteration(fresh_formula),
+        per_iteration(fresh_solve)
+    );
+    println!(
+        "cached: formula {:?}, solve {:?} per iteration",
+        per_iteration(cached_formula),
+        per_iteration(cached_solve)
+    );
+
+    for encoding in [Encoding::Pairwise, Encoding::Sequential] {
+        let options = SolveOptions {
+            encoding,
+            ..Default::default()
+        };
+        let dimacs = grid.to_dimacs_with(&options);
+        let header = dimacs.lines().next().unwrap();
+        let mut solve = Duration::ZERO;
+        for _ in 0..ITERATIONS {
+            solve += grid.solve_with(&options).unwrap().1.solve_time;
+        }
+        println!(
+            "{encoding:?}: {header}, solve {:?} per iteration",
+            per_iteration(solve)
+        );
+    }
+
+    let minimal: Grid = MINIMAL.parse().unwrap();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        assert!(is_minimal_rebuilt(&minimal));
+    }
+    let rebuilt = start.elapsed();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        assert!(minimal.is_minimal().unwrap());
+    }
+    let assumptions = start.elapsed();
+    println!(
+        "is_minimal: rebuilt {:?}, assumptions {:?} per iteration",
+        per_iteration(rebuilt),
+        per_iteration(assumptions)
+    );
+
+    // The neighbors of the puzzle that miss one of its clues
+    let solution = grid.clone().solve().unwrap();
+    let neighbors = grid
+        .filled_cells()
+        .map(|(x, y, _)| {
+            let mut neighbor = grid.clone();
+            neighbor[(x, y)] = None;
+            neighbor
+        })
+        .collect::<Vec<_>>();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for neighbor in &neighbors {
+            neighbor.clone().solve().unwrap();
+        }
+    }
+    let cold = start.elapsed();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for neighbor in &neighbors {
+            neighbor.solve_warm(&solution).unwrap();
+        }
+    }
+    let warm = start.elapsed();
+    println!(
+        "{} neighbors: cold {:?}, warm {:?} per iteration",
+        neighbors.len(),
+        per_iteration(cold),
+        per_iteration(warm)
+    );
+}
@@ -0,0 +1,177 @@
+//! Solving within a wall-clock budget, falling back to the cells that are known for sure, and
+//! solving that can be cancelled.
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use varisat::{
+    checker::{CheckedProofStep, CheckerData, ProofProcessor},
+    Solver,
+};
+
+use crate::{last_model, propagate, Grid, SolveError, SolveOptions, NUM_VARS};
+
+/// The result of [`Grid::solve_anytime`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnytimeSolution {
+    /// The solution, or as much of it as could be determined within the budget.
+    pub grid: Grid,
+    /// Whether `grid` is a complete solution.
+    pub complete: bool,
+}
+
+/// Aborts the solver once the deadline has passed. varisat has no way to interrupt a solve, but
+/// it does stop when a proof processor fails, and those see a step for every learned clause.
+struct Deadline(Instant);
+
+impl ProofProcessor for Deadline {
+    fn process_step(&mut self, _: &CheckedProofStep, _: CheckerData) -> Result<(), anyhow::Error> {
+        if Instant::now() >= self.0 {
+            return Err(anyhow::anyhow!("time budget exceeded"));
+        }
+        Ok(())
+    }
+}
+
+/// Aborts the solver once the flag is set, in the same way as [`Deadline`].
+struct Cancel<'a>(&'a AtomicBool);
+
+impl ProofProcessor for Cancel<'_> {
+    fn process_step(&mut self, _: &CheckedProofStep, _: CheckerData) -> Result<(), anyhow::Error> {
+        if self.0.load(Ordering::Relaxed) {
+            return Err(anyhow::anyhow!("cancelled"));
+        }
+        Ok(())
+    }
+}
+
+impl Grid {
+    /// Solves the grid like [`Grid::solve`], but returns [`SolveError::Cancelled`] once `cancel`
+    /// is set, for example by another thread when the result is no longer needed. The flag is
+    /// checked before solving and whenever the solver learns a clause, so a hard solve stops
+    /// quickly, but one that needs no search may still finish after the flag is set.
+    pub fn solve_cancellable(&self, cancel: &AtomicBool) -> Result<Grid, SolveError> {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(SolveError::Cancelled);
+        }
+        let options = SolveOptions::default();
+        let mut processor = Cancel(cancel);
+        let mut solver = Solver::new();
+        solver.add_proof_processor(&mut processor);
+        self.add_formula_to(&mut solver, &options);
+        match solver.solve() {
+            Ok(true) => self.clone().apply_model(&last_model(&solver)?),
+            Ok(false) => Err(SolveError::Unsatisfiable),
+            Err(_) if cancel.load(Ordering::Relaxed) => Err(SolveError::Cancelled),
+            Err(e) => Err(SolveError::InternalInconsistency(e.to_string())),
+        }
+    }
+
+    /// Solves the grid, giving up after `budget`. If the solver does not finish in time, the
+    /// result contains only the cells implied by unit propagation over the formula, so every
+    /// filled in cell is part of every solution. The solver checks the deadline whenever it
+    /// learns a clause, so it may overshoot the budget slightly.
+    pub fn solve_anytime(
+        &self,
+        options: &SolveOptions,
+        budget: Duration,
+    ) -> Result<AnytimeSolution, SolveError> {
+        let deadline = Instant::now() + budget;
+        options.validate()?;
+
+        // Cells that are known without search
+        let Some(assignment) = propagate(&self.formula(options)) else {
+            return Err(SolveError::Unsatisfiable);
+        };
+        let implied = assignment
+            .iter()
+            .take(NUM_VARS)
+            .enumerate()
+            .filter(|(_, &value)| value == Some(true))
+            .map(|(index, _)| varisat::Var::from_index(index).positive())
+            .collect::<Vec<_>>();
+        let partial = self.clone().apply_model(&implied)?;
+        if partial.is_complete() || Instant::now() >= deadline {
+            debug!("returning {} implied cells", partial.clue_count());
+            let complete = partial.is_complete();
+            return Ok(AnytimeSolution {
+                grid: partial,
+                complete,
+            });
+        }
+
+        let mut processor = Deadline(deadline);
+        let mut solver = Solver::new();
+        solver.add_proof_processor(&mut processor);
+        self.add_formula_to(&mut solver, options);
+        match solver.solve() {
+            Ok(true) => Ok(AnytimeSolution {
+                grid: self.clone().apply_model(&last_model(&solver)?)?,
+                complete: true,
+            }),
+            Ok(false) => Err(SolveError::Unsatisfiable),
+            Err(_) if Instant::now() >= deadline => {
+                debug!(
+                    "out of time, returning {} implied cells",
+                    partial.clue_count()
+                );
+                Ok(AnytimeSolution {
+                    grid: partial,
+                    complete: false,
+                })
+            }
+            Err(e) => Err(SolveError::InternalInconsistency(e.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_solve_anytime() {
+        let grid: Grid =
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  "
+                .parse()
+                .unwrap();
+        let solution = grid.clone().solve().unwrap();
+
+        // Without any time only the cells known up front are filled in
+        let result = grid
+            .solve_anytime(&SolveOptions::default(), Duration::ZERO)
+            .unwrap();
+        assert!(!result.complete);
+        assert!(result.grid.clue_count() >= grid.clue_count());
+        assert!(result
+            .grid
+            .filled_cells()
+            .all(|(x, y, d)| solution.get(x, y) == Some(d)));
+
+        let result = grid
+            .solve_anytime(&SolveOptions::default(), Duration::from_secs(60))
+            .unwrap();
+        assert!(result.complete);
+        assert_eq!(result.grid, solution);
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert_eq!(
+            contradictory.solve_anytime(&SolveOptions::default(), Duration::from_secs(60)),
+            Err(SolveError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn test_solve_cancellable() {
+        let grid: Grid =
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  "
+                .parse()
+                .unwrap();
+        let cancel = AtomicBool::new(false);
+        assert_eq!(grid.solve_cancellable(&cancel), grid.clone().solve());
+
+        cancel.store(true, Ordering::Relaxed);
+        assert_eq!(grid.solve_cancellable(&cancel), Err(SolveError::Cancelled));
+    }
+}
@@ -0,0 +1,147 @@
+//! An abstraction over the engine that solves the formula of a grid, so backends other than
+//! varisat can be plugged in.
+use std::time::Instant;
+
+use varisat::{CnfFormula, ExtendFormula, Lit, Solver};
+
+use crate::{add_sudoku_rules, is_valid_solution, var_for, Grid, SolveError, SolveStats};
+
+/// A backend that finds a model of the formula of a grid, in which the cells are encoded by the
+/// variables of [`var_for`].
+pub trait SudokuSolver {
+    /// Adds the clauses of `formula`, which encode the rules of sudoku.
+    fn add_formula(&mut self, formula: &CnfFormula);
+
+    /// Adds the clue that cell `(x, y)` contains digit `d`.
+    fn add_clue(&mut self, x: usize, y: usize, d: u8);
+
+    /// Solves everything added so far. Returns the literals of a model, or `None` if there is no
+    /// solution.
+    fn solve(&mut self) -> Result<Option<Vec<Lit>>, SolveError>;
+}
+
+impl SudokuSolver for Solver<'_> {
+    fn add_formula(&mut self, formula: &CnfFormula) {
+        Solver::add_formula(self, formula);
+    }
+
+    fn add_clue(&mut self, x: usize, y: usize, d: u8) {
+        self.add_clause(&[var_for(x, y, d as usize).positive()]);
+    }
+
+    fn solve(&mut self) -> Result<Option<Vec<Lit>>, SolveError> {
+        match Solver::solve(self) {
+            Ok(true) => Ok(self.model()),
+            Ok(false) => Ok(None),
+            Err(e) => Err(SolveError::InternalInconsistency(e.to_string())),
+        }
+    }
+}
+
+impl Grid {
+    /// Solves the grid with the given backend. [`Grid::solve`] does the same with varisat.
+    pub fn solve_using(self, backend: impl SudokuSolver) -> Result<Grid, SolveError> {
+        self.solve_using_with_stats(backend)
+            .map(|(solution, _)| solution)
+    }
+
+    /// Solves the grid like [`Grid::solve_using`] and reports how long loading the formula and
+    /// solving took.
+    pub(crate) fn solve_using_with_stats(
+        &self,
+        mut backend: impl SudokuSolver,
+    ) -> Result<(Grid, SolveStats), SolveError> {
+        let start_formula = Instant::now();
+        let mut rules = CnfFormula::new();
+        add_sudoku_rules(&mut rules);
+        backend.add_formula(&rules);
+        for (x, y, d) in self.filled_cells() {
+            backend.add_clue(x, y, d);
+        }
+        let formula_time = start_formula.elapsed();
+
+        let start_solve = Instant::now();
+        let model = backend.solve()?.ok_or(SolveError::Unsatisfiable)?;
+        let stats = SolveStats {
+            solve_time: start_solve.elapsed(),
+            formula_time,
+            ..Default::default()
+        };
+        let solution = Grid::empty().apply_model(&model)?;
+
+        // Do not trust the backend to respect the clues
+        if !is_valid_solution(&solution)
+            || self
+                .filled_cells()
+                .any(|(x, y, d)| solution.get(x, y) != Some(d))
+        {
+            return Err(SolveError::InternalInconsistency(
+                "the backend returned a model that is not a solution".to_string(),
+            ));
+        }
+        Ok((solution, stats))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Ignores the formula and answers with a fixed grid.
+    struct MockSolver {
+        answer: Option<Grid>,
+        clues: Vec<(usize, usize, u8)>,
+    }
+
+    impl SudokuSolver for &mut MockSolver {
+        fn add_formula(&mut self, formula: &CnfFormula) {
+            assert_eq!(formula.var_count(), 729);
+        }
+
+        fn add_clue(&mut self, x: usize, y: usize, d: u8) {
+            self.clues.push((x, y, d));
+        }
+
+        fn solve(&mut self) -> Result<Option<Vec<Lit>>, SolveError> {
+            Ok(self.answer.as_ref().map(|answer| {
+                answer
+                    .filled_cells()
+                    .map(|(x, y, d)| var_for(x, y, d as usize).positive())
+                    .collect()
+            }))
+        }
+    }
+
+    #[test]
+    fn test_solve_using() {
+        let puzzle: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let solution = puzzle.clone().solve().unwrap();
+        assert_eq!(
+            puzzle.clone().solve_using(Solver::new()),
+            Ok(solution.clone())
+        );
+
+        let mut mock = MockSolver {
+            answer: Some(solution.clone()),
+            clues: Vec::new(),
+        };
+        assert_eq!(puzzle.clone().solve_using(&mut mock), Ok(solution));
+        assert_eq!(mock.clues, puzzle.filled_cells().collect::<Vec<_>>());
+
+        // A model that ignores the clues is rejected
+        mock.answer = Some(Grid::generate_filled(1));
+        assert!(matches!(
+            puzzle.clone().solve_using(&mut mock),
+            Err(SolveError::InternalInconsistency(_))
+        ));
+
+        mock.answer = None;
+        assert_eq!(
+            puzzle.solve_using(&mut mock),
+            Err(SolveError::Unsatisfiable)
+        );
+    }
+}
@@ -0,0 +1,321 @@
+//! A solver backend that searches the grid directly instead of going through a SAT solver.
+use std::collections::HashSet;
+
+use varisat::{CnfFormula, Lit, Var};
+
+use crate::{
+    add_sudoku_rules, box_index, cell_for, Grid, SolveError, SolveOptions, SolveStats,
+    SudokuSolver, NUM_VARS,
+};
+
+/// Mask of the nine digits in the row, column and box masks.
+const ALL_DIGITS: u16 = 0x1ff;
+
+/// A [`SudokuSolver`] that fills in the grid by backtracking. It always continues with the empty
+/// cell with the fewest candidates, so cells with a single candidate are propagated before
+/// anything is guessed.
+///
+/// The rules of sudoku are built in. Other clauses passed to [`SudokuSolver::add_formula`] are
+/// checked whenever one of their cells is filled in, so they can only use the 729 cell variables.
+pub struct BacktrackingSolver {
+    cells: [Option<u8>; 81],
+    /// Bit `d` is set if digit `d` is used in the row, column or box.
+    rows: [u16; 9],
+    columns: [u16; 9],
+    boxes: [u16; 9],
+    clauses: Vec<Vec<Lit>>,
+    /// The indices of the clauses that mention a cell, for each cell.
+    watches: Vec<Vec<usize>>,
+    /// Set when two clues contradict each other.
+    contradiction: bool,
+    /// Set when a clause uses a variable other than the cell variables.
+    unsupported: bool,
+    /// The decision level at which each cell was filled in, see [`Grid::solve_with_levels`].
+    levels: [usize; 81],
+}
+
+impl Default for BacktrackingSolver {
+    fn default() -> Self {
+        Self {
+            cells: [None; 81],
+            rows: [0; 9],
+            columns: [0; 9],
+            boxes: [0; 9],
+            clauses: Vec::new(),
+            watches: vec![Vec::new(); 81],
+            contradiction: false,
+            unsupported: false,
+            levels: [0; 81],
+        }
+    }
+}
+
+impl BacktrackingSolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the digits that can still be placed in the empty cell `i`.
+    fn candidates(&self, i: usize) -> u16 {
+        let (x, y) = (i % 9, i / 9);
+        !(self.rows[y] | self.columns[x] | self.boxes[box_index(x, y)]) & ALL_DIGITS
+    }
+
+    fn toggle(&mut self, i: usize, d: u8) {
+        let (x, y) = (i % 9, i / 9);
+        self.rows[y] ^= 1 << d;
+        self.columns[x] ^= 1 << d;
+        self.boxes[box_index(x, y)] ^= 1 << d;
+    }
+
+    /// Returns whether `lit` is false for the cells filled in so far.
+    fn is_false(&self, lit: Lit) -> bool {
+        let (x, y, d) = cell_for(lit.var());
+        self.cells[y * 9 + x].is_some_and(|digit| (digit as usize == d) != lit.is_positive())
+    }
+
+    /// Returns whether the clauses that mention cell `i` can still be satisfied.
+    fn clauses_hold(&self, i: usize) -> bool {
+        self.watches[i]
+            .iter()
+            .all(|&c| !self.clauses[c].iter().all(|&lit| self.is_false(lit)))
+    }
+
+    /// Fills in the remaining empty cells, `level` being the number of guesses made so far.
+    /// Returns whether that succeeded; if not the cells are left as they were.
+    fn search(&mut self, level: usize) -> bool {
+        let mut best: Option<(usize, u16)> = None;
+        for i in (0..81).filter(|&i| self.cells[i].is_none()) {
+            let candidates = self.candidates(i);
+            if candidates == 0 {
+                return false;
+            }
+            if best.is_none_or(|(_, b)| candidates.count_ones() < b.count_ones()) {
+                best = Some((i, candidates));
+            }
+        }
+
+        let Some((i, candidates)) = best else {
+            return true;
+        };
+        // A cell with a single candidate is forced, the others are a guess
+        let level = if candidates.count_ones() == 1 {
+            level
+        } else {
+            level + 1
+        };
+        for d in (0..9).filter(|&d| candidates & (1 << d) != 0) {
+            self.cells[i] = Some(d);
+            self.levels[i] = level;
+            self.toggle(i, d);
+            if self.clauses_hold(i) && self.search(level) {
+                return true;
+            }
+            self.toggle(i, d);
+            self.cells[i] = None;
+        }
+        false
+    }
+}
+
+impl SudokuSolver for BacktrackingSolver {
+    /// Clauses that use a variable other than the 729 cell variables are rejected when solving.
+    fn add_formula(&mut self, formula: &CnfFormula) {
+        let mut rules = CnfFormula::new();
+        add_sudoku_rules(&mut rules);
+        let rules = rules.iter().collect::<HashSet<_>>();
+
+        for clause in formula.iter().filter(|clause| !rules.contains(clause)) {
+            if clause.iter().any(|lit| lit.index() >= NUM_VARS) {
+                self.unsupported = true;
+                continue;
+            }
+            let mut cells = clause
+                .iter()
+                .map(|lit| {
+                    let (x, y, _) = cell_for(lit.var());
+                    y * 9 + x
+                })
+                .collect::<Vec<_>>();
+            cells.dedup();
+            for cell in cells {
+                self.watches[cell].push(self.clauses.len());
+            }
+            self.clauses.push(clause.to_vec());
+        }
+    }
+
+    fn add_clue(&mut self, x: usize, y: usize, d: u8) {
+        let i = y * 9 + x;
+        match self.cells[i] {
+            Some(prev) => self.contradiction |= prev != d,
+            None if self.candidates(i) & (1 << d) == 0 => self.contradiction = true,
+            None => {
+                self.cells[i] = Some(d);
+                self.toggle(i, d);
+            }
+        }
+    }
+
+    fn solve(&mut self) -> Result<Option<Vec<Lit>>, SolveError> {
+        if self.unsupported {
+            return Err(SolveError::InvalidConstraint(
+                "the backtracking solver only supports the cell variables".to_string(),
+            ));
+        }
+        let clues_hold = (0..81)
+            .filter(|&i| self.cells[i].is_some())
+            .all(|i| self.clauses_hold(i));
+        if self.contradiction || !clues_hold || !self.search(0) {
+            return Ok(None);
+        }
+        Ok(Some(
+            (0..NUM_VARS)
+                .map(|index| {
+                    let var = Var::from_index(index);
+                    let (x, y, d) = cell_for(var);
+                    var.lit(self.cells[y * 9 + x] == Some(d as u8))
+                })
+                .collect(),
+        ))
+    }
+}
+
+impl Grid {
+    /// Solves the grid with the [`BacktrackingSolver`] instead of varisat.
+    pub fn solve_backtracking(self) -> Result<Grid, SolveError> {
+        self.solve_using(BacktrackingSolver::new())
+    }
+
+    /// Solves the grid and returns, for every cell in row-major order, the decision level at
+    /// which it was filled in: the number of guesses made up to and including that cell. The
+    /// clues are at level 0, and so are the cells that are left with a single candidate before
+    /// the first guess.
+    ///
+    /// varisat does not expose the decision levels of its search, so the levels come from the
+    /// [`BacktrackingSolver`], which fills in the cell with the fewest candidates first and only
+    /// counts a cell with more than one candidate as a guess. The levels describe the path to the
+    /// solution that was found, without the guesses that were undone along the way.
+    pub fn solve_with_levels(&self) -> Result<(Grid, [usize; 81]), SolveError> {
+        let mut solver = BacktrackingSolver::new();
+        for (x, y, d) in self.filled_cells() {
+            solver.add_clue(x, y, d);
+        }
+        let model = solver.solve()?.ok_or(SolveError::Unsatisfiable)?;
+        Ok((self.clone().apply_model(&model)?, solver.levels))
+    }
+
+    /// Solves the grid with both varisat, as in [`Grid::solve_with`], and the
+    /// [`BacktrackingSolver`], and returns the solution together with the statistics of varisat
+    /// and of the backtracking solver, in that order.
+    ///
+    /// Returns [`SolveError::InternalInconsistency`] if the two disagree. A puzzle with several
+    /// solutions may legitimately get a different one from each backend, so this is only a
+    /// cross-check for puzzles with a unique solution.
+    pub fn solve_compare(&self) -> Result<(Grid, SolveStats, SolveStats), SolveError> {
+        let sat = self.solve_with(&SolveOptions::default());
+        let backtracking = self.solve_using_with_stats(BacktrackingSolver::new());
+        match (sat, backtracking) {
+            (Ok((solution, sat_stats)), Ok((other, backtracking_stats))) if solution == other => {
+                Ok((solution, sat_stats, backtracking_stats))
+            }
+            (Ok(_), Ok(_)) => Err(SolveError::InternalInconsistency(
+                "the backends found different solutions".to_string(),
+            )),
+            (Err(SolveError::Unsatisfiable), Err(SolveError::Unsatisfiable)) => {
+                Err(SolveError::Unsatisfiable)
+            }
+            (Err(SolveError::Unsatisfiable), Ok(_)) | (Ok(_), Err(SolveError::Unsatisfiable)) => {
+                Err(SolveError::InternalInconsistency(
+                    "only one of the backends found a solution".to_string(),
+                ))
+            }
+            (Err(SolveError::Unsatisfiable), Err(e)) | (Err(e), _) | (_, Err(e)) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use varisat::ExtendFormula;
+
+    use super::*;
+
+    #[test]
+    fn test_solve_backtracking() {
+        let puzzles = [
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79",
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  ",
+            "  3 2 6  9  3 5  1  18 64    81 29  7       8  67 82    26 95  8  2 3  9  5 1 3  ",
+        ];
+        for puzzle in puzzles {
+            let puzzle: Grid = puzzle.parse().unwrap();
+            assert_eq!(puzzle.clone().solve_backtracking(), puzzle.clone().solve());
+        }
+
+        let solution = Grid::generate_filled(9);
+        assert_eq!(solution.clone().solve_backtracking(), Ok(solution));
+        assert!(crate::is_valid_solution(
+            &Grid::empty().solve_backtracking().unwrap()
+        ));
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert_eq!(
+            contradictory.solve_backtracking(),
+            Err(SolveError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn test_solve_with_levels() {
+        let puzzle: Grid =
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  "
+                .parse()
+                .unwrap();
+        let (solution, levels) = puzzle.solve_with_levels().unwrap();
+        assert_eq!(Ok(solution), puzzle.clone().solve());
+        for (x, y, _) in puzzle.filled_cells() {
+            assert_eq!(levels[y * 9 + x], 0);
+        }
+        assert!(levels.iter().any(|&level| level > 0));
+
+        // Singles solve this one without guessing
+        let easy: Grid =
+            "  3 2 6  9  3 5  1  18 64    81 29  7       8  67 82    26 95  8  2 3  9  5 1 3  "
+                .parse()
+                .unwrap();
+        assert_eq!(easy.solve_with_levels().unwrap().1, [0; 81]);
+    }
+
+    #[test]
+    fn test_solve_compare() {
+        let puzzles = [
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79",
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  ",
+        ];
+        for puzzle in puzzles {
+            let puzzle: Grid = puzzle.parse().unwrap();
+            let (solution, _, _) = puzzle.solve_compare().unwrap();
+            assert_eq!(Ok(solution), puzzle.clone().solve());
+        }
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert!(matches!(
+            contradictory.solve_compare(),
+            Err(SolveError::Unsatisfiable)
+        ));
+    }
+
+    #[test]
+    fn test_backtracking_clauses() {
+        // An extra clause that rules out a 1 in the top left corner
+        let mut formula = CnfFormula::new();
+        formula.add_clause(&[Var::from_index(0).negative()]);
+        let mut solver = BacktrackingSolver::new();
+        solver.add_formula(&formula);
+        let model = solver.solve().unwrap().unwrap();
+        let solution = Grid::empty().apply_model(&model).unwrap();
+        assert!(crate::is_valid_solution(&solution));
+        assert_ne!(solution.get(0, 0), Some(0));
+    }
+}
@@ -0,0 +1,120 @@
+//! Solving many puzzles in one go.
+use std::{
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+
+use crate::{Grid, SolveError};
+
+/// Reads puzzles from `reader`, one per line in the format accepted by [`Grid::from_str`], and
+/// writes the solution of each to `writer` as soon as it is found. Every non-empty input line
+/// results in exactly one output line: the solution in the same format, or a line starting with
+/// `error: ` if the puzzle could not be parsed or has no solution.
+pub fn solve_stream(reader: impl BufRead, writer: impl Write) -> io::Result<()> {
+    solve_stream_with_progress(reader, writer, |_| {})
+}
+
+/// How far [`solve_stream_with_progress`] got.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StreamProgress {
+    /// Number of puzzles written to the output.
+    pub puzzles: usize,
+    /// Number of bytes read from the input, including line endings.
+    pub bytes: u64,
+}
+
+/// Solves the puzzles of `reader` like [`solve_stream`], calling `progress` after writing each
+/// output line. Comparing [`StreamProgress::bytes`] to the length of the input gives the fraction
+/// of the puzzles that is done.
+pub fn solve_stream_with_progress(
+    mut reader: impl BufRead,
+    mut writer: impl Write,
+    mut progress: impl FnMut(&StreamProgress),
+) -> io::Result<()> {
+    let mut state = StreamProgress::default();
+    let mut buffer = String::new();
+    loop {
+        buffer.clear();
+        let read = reader.read_line(&mut buffer)?;
+        if read == 0 {
+            return Ok(());
+        }
+        state.bytes += read as u64;
+        let line = buffer.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            continue;
+        }
+
+        match Grid::from_str(line)
+            .map_err(SolveError::Parse)
+            .and_then(Grid::solve)
+        {
+            Ok(solution) => writeln!(writer, "{}", solution.to_line())?,
+            Err(SolveError::Unsatisfiable) => writeln!(writer, "error: no solution")?,
+            Err(SolveError::Cancelled) => writeln!(writer, "error: cancelled")?,
+            Err(
+                SolveError::Parse(message)
+                | SolveError::InvalidConstraint(message)
+                | SolveError::InternalInconsistency(message)
+                | SolveError::Proof(message),
+            ) => writeln!(writer, "error: {message}")?,
+        }
+        state.puzzles += 1;
+        progress(&state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_solve_stream() {
+        let input = concat!(
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79\n",
+            "\n",
+            "11                                                                               \r\n",
+            "not a puzzle\n",
+            "  3 2 6  9  3 5  1  18 64    81 29  7       8  67 82    26 95  8  2 3  9  5 1 3  \n",
+        );
+
+        let mut output = Vec::new();
+        solve_stream(input.as_bytes(), &mut output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+        );
+        assert_eq!(lines[1], "error: no solution");
+        assert!(lines[2].starts_with("error: "));
+        assert_eq!(
+            lines[3],
+            "483921657967345821251876493548132976729564138136798245372689514814253769695417382"
+        );
+    }
+
+    #[test]
+    fn test_solve_stream_with_progress() {
+        let input = "11\n\nnot a puzzle\r\n";
+        let mut reports = Vec::new();
+        let mut output = Vec::new();
+        solve_stream_with_progress(input.as_bytes(), &mut output, |p| reports.push(*p)).unwrap();
+        assert_eq!(
+            reports,
+            vec![
+                StreamProgress {
+                    puzzles: 1,
+                    bytes: 3
+                },
+                StreamProgress {
+                    puzzles: 2,
+                    bytes: 18
+                },
+            ]
+        );
+        assert_eq!(String::from_utf8(output).unwrap().lines().count(), 2);
+    }
+}
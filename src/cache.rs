@@ -0,0 +1,145 @@
+//! Memoizing solutions across equivalent puzzles.
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{Grid, SolveError};
+
+/// A solved puzzle, together with its fingerprint so most lookups can skip the equivalence check.
+struct Entry {
+    fingerprint: u64,
+    puzzle: Grid,
+    solution: Grid,
+}
+
+#[derive(Default)]
+struct State {
+    /// The most recently used entry comes first.
+    entries: VecDeque<Entry>,
+    hits: u64,
+    misses: u64,
+}
+
+/// A least recently used cache of solutions, shared by puzzles that are equivalent (see
+/// [`Grid::equivalent_to`]). A puzzle that is equivalent to a cached one is answered by applying
+/// the same relabeling and reordering to the cached solution, without running the solver.
+///
+/// The cache holds at most the number of puzzles given to [`SolverCache::new`]; when it is full
+/// the least recently used one is dropped. Only solutions are cached, puzzles without one are
+/// solved again every time.
+///
+/// All methods take `&self`, so a cache can be shared between threads, for example in an `Arc`.
+/// The lock is not held while solving: threads that miss on the same puzzle at the same time
+/// both solve it.
+pub struct SolverCache {
+    capacity: usize,
+    state: Mutex<State>,
+}
+
+impl SolverCache {
+    /// Creates an empty cache that holds up to `capacity` puzzles.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Solves the grid like [`Grid::solve`], reusing the solution of an equivalent puzzle if one
+    /// is cached.
+    pub fn solve(&self, grid: &Grid) -> Result<Grid, SolveError> {
+        let fingerprint = grid.fingerprint();
+        {
+            let mut state = self.lock();
+            let found = state.entries.iter().enumerate().find_map(|(i, entry)| {
+                (entry.fingerprint == fingerprint)
+                    .then(|| entry.puzzle.equivalence_transform(grid))
+                    .flatten()
+                    .map(|transform| (i, transform.apply(&entry.solution)))
+            });
+            if let Some((i, solution)) = found {
+                if let Some(entry) = state.entries.remove(i) {
+                    state.entries.push_front(entry);
+                }
+                state.hits += 1;
+                return Ok(solution);
+            }
+            state.misses += 1;
+        }
+
+        let solution = grid.clone().solve()?;
+        let mut state = self.lock();
+        state.entries.push_front(Entry {
+            fingerprint,
+            puzzle: grid.clone(),
+            solution: solution.clone(),
+        });
+        state.entries.truncate(self.capacity);
+        Ok(solution)
+    }
+
+    /// Number of calls to [`SolverCache::solve`] that were answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.lock().hits
+    }
+
+    /// Number of calls to [`SolverCache::solve`] that had to run the solver.
+    pub fn misses(&self) -> u64 {
+        self.lock().misses
+    }
+
+    /// Number of puzzles in the cache.
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// A panic in another thread cannot leave the state half updated, so a poisoned lock is
+    /// still usable.
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_solver_cache() {
+        let puzzle: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let cache = SolverCache::new(2);
+        assert_eq!(cache.solve(&puzzle), puzzle.clone().solve());
+        assert_eq!((cache.hits(), cache.misses()), (0, 1));
+
+        // An equivalent puzzle gets the transformed solution
+        let equivalent = puzzle.random_equivalent(3);
+        let solution = cache.solve(&equivalent).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+        assert!(crate::is_valid_solution(&solution));
+        assert!(equivalent
+            .filled_cells()
+            .all(|(x, y, d)| solution.get(x, y) == Some(d)));
+
+        // Puzzles without a solution are not cached
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert_eq!(cache.solve(&contradictory), Err(SolveError::Unsatisfiable));
+        assert_eq!(cache.len(), 1);
+
+        // The least recently used puzzle is dropped once the cache is full
+        let others = [Grid::generate_filled(1), Grid::generate_filled(2)];
+        for other in &others {
+            cache.solve(other).unwrap();
+        }
+        assert_eq!(cache.len(), 2);
+        cache.solve(&puzzle).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 5));
+    }
+}
@@ -0,0 +1,134 @@
+//! A C interface to the solver, behind the `ffi` feature.
+//!
+//! Build a library that C programs can link against with
+//! `cargo rustc --release --features ffi --crate-type cdylib` (or `staticlib`). The functions
+//! only borrow the buffers they are given: the caller owns every pointer passed in, and the
+//! strings returned by [`sudosat_status_message`] are static and must not be freed.
+//!
+//! A C declaration of the interface:
+//!
+//! ```c
+//! int sudosat_solve(const char *input, char *out);
+//! const char *sudosat_status_message(int status);
+//! ```
+use std::{
+    ffi::{c_char, c_int, CStr},
+    str::FromStr,
+};
+
+use crate::{Grid, SolveError};
+
+/// The puzzle was solved.
+pub const SUDOSAT_OK: c_int = 0;
+/// The puzzle has no solution.
+pub const SUDOSAT_NO_SOLUTION: c_int = 1;
+/// The input is not valid UTF-8 or not a grid in the format of [`Grid::from_str`].
+pub const SUDOSAT_INVALID_INPUT: c_int = 2;
+/// One of the pointers is null.
+pub const SUDOSAT_NULL_POINTER: c_int = 3;
+/// The solver failed, see [`SolveError::InternalInconsistency`].
+pub const SUDOSAT_INTERNAL_ERROR: c_int = 4;
+
+/// Size of the buffer that [`sudosat_solve`] writes the solution to: 81 digits and the
+/// terminating NUL.
+pub const SUDOSAT_SOLUTION_LEN: usize = 82;
+
+/// Solves the puzzle in the NUL-terminated string `input`, written as 81 characters in the
+/// format of [`Grid::from_str`] with a space for every empty cell. On success writes the solution
+/// to `out` as 81 digits `1`-`9` followed by a NUL and returns [`SUDOSAT_OK`]; otherwise `out` is
+/// left untouched and one of the other status codes is returned.
+///
+/// # Safety
+///
+/// `input` must be null or point to a NUL-terminated string, and `out` must be null or point to
+/// a writable buffer of at least [`SUDOSAT_SOLUTION_LEN`] bytes. Neither pointer is kept after
+/// the call returns.
+#[no_mangle]
+pub unsafe extern "C" fn sudosat_solve(input: *const c_char, out: *mut c_char) -> c_int {
+    if input.is_null() || out.is_null() {
+        return SUDOSAT_NULL_POINTER;
+    }
+    // SAFETY: `input` is a NUL-terminated string according to the contract
+    let Ok(input) = unsafe { CStr::from_ptr(input) }.to_str() else {
+        return SUDOSAT_INVALID_INPUT;
+    };
+    let Ok(grid) = Grid::from_str(input) else {
+        return SUDOSAT_INVALID_INPUT;
+    };
+    let solution = match grid.solve() {
+        Ok(solution) => solution,
+        Err(SolveError::Unsatisfiable) => return SUDOSAT_NO_SOLUTION,
+        Err(SolveError::Parse(_) | SolveError::InvalidConstraint(_)) => {
+            return SUDOSAT_INVALID_INPUT
+        }
+        Err(_) => return SUDOSAT_INTERNAL_ERROR,
+    };
+
+    let mut line = solution.to_line().into_bytes();
+    line.push(0);
+    // SAFETY: `out` has room for the 81 digits and the NUL according to the contract, and cannot
+    // overlap the freshly allocated `line`
+    unsafe { std::ptr::copy_nonoverlapping(line.as_ptr().cast(), out, line.len()) };
+    SUDOSAT_OK
+}
+
+/// Returns a static, NUL-terminated description of a status code returned by the functions of
+/// this module. The string must not be freed.
+#[no_mangle]
+pub extern "C" fn sudosat_status_message(status: c_int) -> *const c_char {
+    let message: &'static CStr = match status {
+        SUDOSAT_OK => c"ok",
+        SUDOSAT_NO_SOLUTION => c"puzzle has no solution",
+        SUDOSAT_INVALID_INPUT => c"invalid input",
+        SUDOSAT_NULL_POINTER => c"null pointer",
+        SUDOSAT_INTERNAL_ERROR => c"internal error",
+        _ => c"unknown status",
+    };
+    message.as_ptr()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{ffi::CString, ptr};
+
+    use super::*;
+
+    /// Calls [`sudosat_solve`] with `input`, returning the status and the output buffer.
+    fn solve(input: &str) -> (c_int, String) {
+        let input = CString::new(input).unwrap();
+        let mut out = [b'#' as c_char; SUDOSAT_SOLUTION_LEN];
+        let status = unsafe { sudosat_solve(input.as_ptr(), out.as_mut_ptr()) };
+        let out = unsafe { CStr::from_ptr(out.as_ptr()) };
+        (status, out.to_str().unwrap_or_default().to_string())
+    }
+
+    #[test]
+    fn test_sudosat_solve() {
+        let (status, out) = solve(
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79",
+        );
+        assert_eq!(status, SUDOSAT_OK);
+        assert_eq!(
+            out,
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+        );
+
+        let (status, _) = solve(&format!("11{}", " ".repeat(79)));
+        assert_eq!(status, SUDOSAT_NO_SOLUTION);
+        assert_eq!(solve("not a puzzle").0, SUDOSAT_INVALID_INPUT);
+
+        let mut out = [0 as c_char; SUDOSAT_SOLUTION_LEN];
+        assert_eq!(
+            unsafe { sudosat_solve(ptr::null(), out.as_mut_ptr()) },
+            SUDOSAT_NULL_POINTER
+        );
+        let input = CString::new(" ".repeat(81)).unwrap();
+        assert_eq!(
+            unsafe { sudosat_solve(input.as_ptr(), ptr::null_mut()) },
+            SUDOSAT_NULL_POINTER
+        );
+
+        let message = unsafe { CStr::from_ptr(sudosat_status_message(SUDOSAT_NO_SOLUTION)) };
+        assert_eq!(message.to_str(), Ok("puzzle has no solution"));
+    }
+}
@@ -0,0 +1,5209 @@
+use std::{
+    collections::hash_map::RandomState,
+    fmt::{Display, Formatter},
+    fs::File,
+    hash::{BuildHasher, Hasher},
+    io::{BufRead, BufWriter},
+    ops::{ControlFlow, Index, IndexMut},
+    path::PathBuf,
+    str::FromStr,
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+use varisat::{ExtendFormula, ProofFormat, Solver};
+
+use crate::rng::Rng;
+
+pub use varisat::{CnfFormula, Lit, Var};
+
+// Forward to the `log` crate when the `log` feature is enabled and compile to nothing otherwise.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        log::debug!($($arg)*);
+    };
+}
+
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        log::trace!($($arg)*);
+    };
+}
+
+// Records a field of the current span when the `tracing` feature is enabled.
+macro_rules! record {
+    ($field:literal, $value:expr) => {
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record($field, $value);
+    };
+}
+
+mod anytime;
+mod backend;
+mod backtracking;
+mod batch;
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod logic;
+mod packed;
+mod quality;
+mod render;
+mod rng;
+#[cfg(feature = "serde")]
+mod serialization;
+mod symmetry;
+mod variant;
+
+pub use anytime::AnytimeSolution;
+pub use backend::SudokuSolver;
+pub use backtracking::BacktrackingSolver;
+pub use batch::{solve_stream, solve_stream_with_progress, StreamProgress};
+#[cfg(feature = "cache")]
+pub use cache::SolverCache;
+pub use logic::{
+    Difficulty, NakedPair, PointingPair, Step, Technique, TechniqueWeights, Unit, GENERATE_ATTEMPTS,
+};
+pub use quality::PuzzleQuality;
+pub use symmetry::{PatternSymmetry, Transform};
+
+pub type Cell = Option<u8>;
+
+/// For every cell in row-major order, whether each of the (0-based) digits is still possible.
+pub type CandidateGrid = [[bool; 9]; 81];
+
+/// Reasons why a grid could not be solved.
+///
+/// The methods that return a `Result` with this error do not panic, whatever grid or options
+/// they are given: malformed input and failures inside the solver are reported as one of these
+/// variants instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolveError {
+    /// There is no assignment of digits that satisfies the rules and the filled in values.
+    Unsatisfiable,
+
+    /// The input could not be parsed as a grid.
+    Parse(String),
+
+    /// One of the constraints in the [`SolveOptions`] is malformed.
+    InvalidConstraint(String),
+
+    /// The solver failed, or the solution decoded from it breaks the rules, which indicates a
+    /// bug in the encoding. Broken solutions are only detected when [`SolveOptions::verify`] is
+    /// set.
+    InternalInconsistency(String),
+
+    /// The proof requested through [`SolveOptions::proof`] could not be written.
+    Proof(String),
+
+    /// The solve was stopped through the flag passed to [`Grid::solve_cancellable`].
+    Cancelled,
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Unsatisfiable => write!(f, "puzzle has no solution"),
+            SolveError::Parse(message) => write!(f, "failed to parse grid: {message}"),
+            SolveError::InvalidConstraint(message) => write!(f, "invalid constraint: {message}"),
+            SolveError::InternalInconsistency(message) => {
+                write!(f, "internal inconsistency: {message}")
+            }
+            SolveError::Proof(message) => write!(f, "failed to write proof: {message}"),
+            SolveError::Cancelled => write!(f, "solve was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+/// How many solutions a grid has.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolutionStatus {
+    None,
+    Unique,
+    Multiple,
+}
+
+impl Display for SolutionStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SolutionStatus::None => "no solution",
+            SolutionStatus::Unique => "unique solution",
+            SolutionStatus::Multiple => "multiple solutions",
+        })
+    }
+}
+
+/// Reasons why a move was rejected by [`Grid::apply_move`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    /// The coordinates lie outside of the grid.
+    OutOfBounds,
+    /// The digit is not in the range 0-8.
+    InvalidDigit,
+    /// The cell already contains a digit.
+    Given,
+    /// The digit already appears in the row, column or box of the cell, at `(x, y)`.
+    Conflict { x: usize, y: usize },
+}
+
+/// A digit that appears more than once in a unit, as reported by [`Grid::validate_clues`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClueConflict {
+    /// The cells `(x, y)` of the unit that hold the digit, in the order of [`Unit::cells`].
+    pub cells: Vec<(usize, usize)>,
+    /// The (0-based) digit.
+    pub digit: u8,
+    pub unit: Unit,
+}
+
+/// How the formula says that each cell holds one digit and that a unit holds each digit at most
+/// once. Every encoding admits the same solutions; they differ in size and in how quickly the
+/// solver finds them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// A binary clause for every pair of literals that may not both be true, 36 clauses for
+    /// nine literals.
+    #[default]
+    Pairwise,
+    /// The sequential counter: auxiliary variables that record whether one of the first
+    /// literals is true, 3n - 4 clauses and n - 1 variables for n literals. varisat has no native
+    /// cardinality constraints, so this is the linear alternative to the pairwise clauses.
+    Sequential,
+}
+
+/// Restricts a cell to either even or odd digits. Parity refers to the digits 1-9 as they are
+/// displayed, so `Even` allows 2, 4, 6 and 8 and `Odd` allows 1, 3, 5, 7 and 9.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parity {
+    Even,
+    Odd,
+}
+
+impl Parity {
+    /// Returns whether the (0-based) digit `d` has this parity.
+    fn allows(self, d: u8) -> bool {
+        (d % 2 == 1) == (self == Parity::Even)
+    }
+}
+
+/// A set of nine cells `(x, y)` that must contain every digit exactly once, on top of the rows,
+/// columns and boxes. Diagonals, windows, disjoint groups and jigsaw pieces are all regions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtraRegion {
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl ExtraRegion {
+    pub fn new(cells: Vec<(usize, usize)>) -> Self {
+        Self { cells }
+    }
+
+    /// The diagonal from the top-left to the bottom-right corner.
+    pub fn main_diagonal() -> Self {
+        Self::new((0..9).map(|i| (i, i)).collect())
+    }
+
+    /// The diagonal from the top-right to the bottom-left corner.
+    pub fn anti_diagonal() -> Self {
+        Self::new((0..9).map(|i| (8 - i, i)).collect())
+    }
+
+    /// One of the four shaded 3x3 windows of windoku, numbered in row-major order.
+    pub fn window(window_idx: usize) -> Self {
+        let (wx, wy) = (1 + (window_idx % 2) * 4, 1 + (window_idx / 2) * 4);
+        Self::new((0..9).map(|i| (wx + i % 3, wy + i / 3)).collect())
+    }
+
+    /// The nine cells at position `i` (in row-major order) within their boxes.
+    pub fn disjoint_group(i: usize) -> Self {
+        Self::new((0..9).map(|b| box_cells(b)[i]).collect())
+    }
+
+    /// Checks that the region consists of nine different cells within the grid.
+    fn validate(&self) -> Result<(), SolveError> {
+        if self.cells.len() != 9 {
+            return Err(SolveError::InvalidConstraint(format!(
+                "region has {} cells instead of 9",
+                self.cells.len()
+            )));
+        }
+        for (i, &(x, y)) in self.cells.iter().enumerate() {
+            if x >= 9 || y >= 9 {
+                return Err(SolveError::InvalidConstraint(format!(
+                    "region cell ({x}, {y}) is out of bounds"
+                )));
+            }
+            if self.cells[..i].contains(&(x, y)) {
+                return Err(SolveError::InvalidConstraint(format!(
+                    "region contains cell ({x}, {y}) twice"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A path of cells `(x, y)` along which the digits strictly increase, starting at the bulb in the
+/// first cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Thermometer(pub Vec<(usize, usize)>);
+
+impl Thermometer {
+    /// Checks that the path consists of at most nine different cells within the grid.
+    fn validate(&self) -> Result<(), SolveError> {
+        if self.0.len() > 9 {
+            return Err(SolveError::InvalidConstraint(format!(
+                "thermometer has {} cells, more than there are digits",
+                self.0.len()
+            )));
+        }
+        for (i, &(x, y)) in self.0.iter().enumerate() {
+            if x >= 9 || y >= 9 {
+                return Err(SolveError::InvalidConstraint(format!(
+                    "thermometer cell ({x}, {y}) is out of bounds"
+                )));
+            }
+            if self.0[..i].contains(&(x, y)) {
+                return Err(SolveError::InvalidConstraint(format!(
+                    "thermometer contains cell ({x}, {y}) twice"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Two equally long lists of cells `(x, y)` that must hold the same digits: the `i`-th cell of
+/// the first list contains the same digit as the `i`-th cell of the second, as in clone sudoku.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CloneRegion(pub Vec<(usize, usize)>, pub Vec<(usize, usize)>);
+
+impl CloneRegion {
+    /// Checks that both lists have the same length and only contain cells within the grid.
+    fn validate(&self) -> Result<(), SolveError> {
+        if self.0.len() != self.1.len() {
+            return Err(SolveError::InvalidConstraint(format!(
+                "clone regions have {} and {} cells",
+                self.0.len(),
+                self.1.len()
+            )));
+        }
+        if let Some((x, y)) = self
+            .0
+            .iter()
+            .chain(&self.1)
+            .find(|&&(x, y)| x >= 9 || y >= 9)
+        {
+            return Err(SolveError::InvalidConstraint(format!(
+                "clone cell ({x}, {y}) is out of bounds"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// What a [`Cage`] requires of the digits in its cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegionConstraint {
+    /// The digits (1-9) add up to the given number.
+    Sum(u32),
+    /// The digits (1-9) multiply to the given number.
+    Product(u32),
+    /// No digit appears twice.
+    Unique,
+}
+
+/// What an [`EdgeClue`] requires of the digits (1-9) in its two cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeRelation {
+    /// The digits add up to the given number, as the X (10) and V (5) of XV sudoku.
+    Sum(u32),
+    /// The digits differ by the given number, as the white dots of Kropki sudoku (1).
+    Diff(u32),
+    /// One digit is the given number times the other, as the black dots of Kropki sudoku (2).
+    Ratio(u32),
+}
+
+impl EdgeRelation {
+    /// Returns whether the digits (1-9) `a` and `b` satisfy the relation, in either order.
+    fn holds(self, a: u32, b: u32) -> bool {
+        match self {
+            EdgeRelation::Sum(n) => a + b == n,
+            EdgeRelation::Diff(n) => a.abs_diff(b) == n,
+            EdgeRelation::Ratio(n) => a * n == b || b * n == a,
+        }
+    }
+}
+
+/// A clue relating the digits of two cells `(x, y)`. In puzzles the cells are usually
+/// orthogonally adjacent, with the clue drawn on the edge between them, but any two cells work.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EdgeClue {
+    pub a: (usize, usize),
+    pub b: (usize, usize),
+    pub relation: EdgeRelation,
+}
+
+impl EdgeClue {
+    pub fn new(a: (usize, usize), b: (usize, usize), relation: EdgeRelation) -> Self {
+        Self { a, b, relation }
+    }
+
+    /// Checks that the clue relates two different cells within the grid.
+    fn validate(&self) -> Result<(), SolveError> {
+        if let Some((x, y)) = [self.a, self.b]
+            .into_iter()
+            .find(|&(x, y)| x >= 9 || y >= 9)
+        {
+            return Err(SolveError::InvalidConstraint(format!(
+                "edge clue cell ({x}, {y}) is out of bounds"
+            )));
+        }
+        if self.a == self.b {
+            return Err(SolveError::InvalidConstraint(format!(
+                "edge clue relates cell ({}, {}) to itself",
+                self.a.0, self.a.1
+            )));
+        }
+        Ok(())
+    }
+
+    /// Adds the clauses for this clue to `formula`: every pair of digits that violates the
+    /// relation is forbidden.
+    fn add_to(&self, formula: &mut impl ExtendFormula) {
+        let ((x1, y1), (x2, y2)) = (self.a, self.b);
+        for d1 in 0..9 {
+            for d2 in (0..9).filter(|&d2| !self.relation.holds(d1 as u32 + 1, d2 as u32 + 1)) {
+                formula.add_clause(&[
+                    var_for(x1, y1, d1).negative(),
+                    var_for(x2, y2, d2).negative(),
+                ]);
+            }
+        }
+    }
+}
+
+/// Largest sum of distinct digits 1-9.
+const MAX_KILLER_SUM: u32 = 45;
+
+/// Returns the sets of `size` distinct digits (1-9) that add up to `sum`, as masks in which bit
+/// `d` stands for the (0-based) digit `d`. The table is computed on first use.
+fn killer_combinations(size: usize, sum: u32) -> &'static [u16] {
+    static TABLE: OnceLock<Vec<Vec<u16>>> = OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        let mut table = vec![Vec::new(); 10 * (MAX_KILLER_SUM as usize + 1)];
+        for mask in 0u16..1 << 9 {
+            let sum = (0..9)
+                .filter(|&d| mask & (1 << d) != 0)
+                .map(|d| d + 1)
+                .sum::<usize>();
+            table[mask.count_ones() as usize * (MAX_KILLER_SUM as usize + 1) + sum].push(mask);
+        }
+        table
+    });
+    if size > 9 || sum > MAX_KILLER_SUM {
+        return &[];
+    }
+    &table[size * (MAX_KILLER_SUM as usize + 1) + sum as usize]
+}
+
+/// A set of cells `(x, y)` whose digits must satisfy a [`RegionConstraint`]. `Sum` and `Product`
+/// allow repeated digits, so a killer cage is a `Sum` and a `Unique` cage over the same cells.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cage {
+    pub cells: Vec<(usize, usize)>,
+    pub constraint: RegionConstraint,
+}
+
+impl Cage {
+    pub fn new(cells: Vec<(usize, usize)>, constraint: RegionConstraint) -> Self {
+        Self { cells, constraint }
+    }
+
+    /// Checks that the cage consists of different cells within the grid.
+    fn validate(&self) -> Result<(), SolveError> {
+        if self.cells.is_empty() {
+            return Err(SolveError::InvalidConstraint(
+                "cage has no cells".to_string(),
+            ));
+        }
+        for (i, &(x, y)) in self.cells.iter().enumerate() {
+            if x >= 9 || y >= 9 {
+                return Err(SolveError::InvalidConstraint(format!(
+                    "cage cell ({x}, {y}) is out of bounds"
+                )));
+            }
+            if self.cells[..i].contains(&(x, y)) {
+                return Err(SolveError::InvalidConstraint(format!(
+                    "cage contains cell ({x}, {y}) twice"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds clauses requiring that combining the digits of the cells with `step`, starting from
+    /// `start`, results in `target`. Auxiliary variable `(i, value)` is true if combining the
+    /// first `i` cells may result in `value`. `step` must never decrease the value, so values
+    /// beyond the target are ruled out right away.
+    fn add_accumulating(
+        &self,
+        formula: &mut impl ExtendFormula,
+        start: u32,
+        target: u32,
+        step: impl Fn(u32, u32) -> u32,
+    ) {
+        let initial = formula.new_var();
+        formula.add_clause(&[initial.positive()]);
+        let mut reachable = vec![(start, initial)];
+
+        for &(x, y) in &self.cells {
+            let mut next: Vec<(u32, Var)> = Vec::new();
+            for &(value, var) in &reachable {
+                for d in 0..9 {
+                    let cell = var_for(x, y, d);
+                    let value = step(value, d as u32 + 1);
+                    if value > target {
+                        formula.add_clause(&[var.negative(), cell.negative()]);
+                        continue;
+                    }
+                    let next_var = match next.iter().find(|&&(v, _)| v == value) {
+                        Some(&(_, next_var)) => next_var,
+                        None => {
+                            let next_var = formula.new_var();
+                            next.push((value, next_var));
+                            next_var
+                        }
+                    };
+                    formula.add_clause(&[var.negative(), cell.negative(), next_var.positive()]);
+                }
+            }
+            reachable = next;
+        }
+
+        for (value, var) in reachable {
+            if value != target {
+                formula.add_clause(&[var.negative()]);
+            }
+        }
+    }
+
+    /// Returns whether `other` constrains the same cells, in any order.
+    fn same_cells(&self, other: &Cage) -> bool {
+        self.cells.len() == other.cells.len()
+            && self.cells.iter().all(|cell| other.cells.contains(cell))
+    }
+
+    /// Rules out the digits that appear in none of the [`killer_combinations`] for a `Sum` cage
+    /// whose digits must also be unique, which helps propagation on killer puzzles.
+    fn add_killer_digits(&self, formula: &mut impl ExtendFormula, sum: u32) {
+        let allowed = killer_combinations(self.cells.len(), sum)
+            .iter()
+            .fold(0, |allowed, &mask| allowed | mask);
+        for &(x, y) in &self.cells {
+            for d in (0..9).filter(|&d| allowed & (1 << d) == 0) {
+                formula.add_clause(&[var_for(x, y, d).negative()]);
+            }
+        }
+    }
+
+    /// Adds the clauses for this cage to `formula`.
+    fn add_to(&self, formula: &mut impl ExtendFormula) {
+        match self.constraint {
+            RegionConstraint::Sum(n) => {
+                self.add_accumulating(formula, 0, n, |a, d| a.saturating_add(d))
+            }
+            RegionConstraint::Product(n) => {
+                self.add_accumulating(formula, 1, n, |a, d| a.saturating_mul(d))
+            }
+            RegionConstraint::Unique => {
+                for d in 0..9 {
+                    for (i, &(x1, y1)) in self.cells.iter().enumerate() {
+                        for &(x2, y2) in &self.cells[i + 1..] {
+                            formula.add_clause(&[
+                                var_for(x1, y1, d).negative(),
+                                var_for(x2, y2, d).negative(),
+                            ]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Options controlling how a grid is solved.
+///
+/// The constraints can be combined freely. Each adds its own clauses on top of the sudoku rules,
+/// in the order of the fields, and a solution satisfies all of them at once. The order only
+/// affects the formula, not which grids are solutions.
+#[derive(Clone, Debug)]
+pub struct SolveOptions {
+    /// Require every row to contain each digit once. Enabled by default; together with
+    /// `enforce_columns` and `enforce_boxes` it can be turned off to see which grids the other
+    /// rules admit on their own.
+    pub enforce_rows: bool,
+
+    /// Require every column to contain each digit once. Enabled by default.
+    pub enforce_columns: bool,
+
+    /// Require every box to contain each digit once. Enabled by default.
+    pub enforce_boxes: bool,
+
+    /// Run unit propagation over the formula before solving and hand the implied values to the
+    /// solver up front.
+    pub preprocess: bool,
+
+    /// How the "at most one" constraints of the cells, rows, columns and boxes are encoded.
+    pub encoding: Encoding,
+
+    /// Cells `(x, y)` that must contain a digit of the given parity.
+    pub parity: Vec<(usize, usize, Parity)>,
+
+    /// Require the nine cells at the same position within their boxes to contain different
+    /// digits, as in disjoint groups sudoku. Shorthand for adding every
+    /// [`ExtraRegion::disjoint_group`] to `extra_regions`.
+    pub disjoint_groups: bool,
+
+    /// Additional regions that must contain every digit exactly once.
+    pub extra_regions: Vec<ExtraRegion>,
+
+    /// Paths along which the digits must strictly increase.
+    pub thermometers: Vec<Thermometer>,
+
+    /// Pairs of regions that must hold the same digits in corresponding cells.
+    pub clones: Vec<CloneRegion>,
+
+    /// Sets of cells with an arithmetic or uniqueness constraint, such as killer cages.
+    pub cages: Vec<Cage>,
+
+    /// Relations between the digits of two cells, such as the dots of Kropki sudoku and the X
+    /// and V of XV sudoku.
+    pub edge_clues: Vec<EdgeClue>,
+
+    /// Candidates narrowed down by the caller, for example by their own propagation. Digits that
+    /// are not a candidate are ruled out, so the solver only has to decide the remaining ones.
+    pub candidates: Option<CandidateGrid>,
+
+    /// Check the decoded solution against the sudoku rules and the filled in cells before
+    /// returning it, as a safety net against bugs in the encoding.
+    pub verify: bool,
+
+    /// Write a proof in DRAT format to this file while solving, one of the formats varisat can
+    /// produce. If the grid has no solution, the proof shows that the formula (see
+    /// [`Grid::to_dimacs`] for the formula without extra constraints) is unsatisfiable and can be
+    /// verified with an external checker such as `drat-trim`. Preprocessing is skipped when a
+    /// proof is requested, so that the proof does not rely on anything but the formula.
+    pub proof: Option<PathBuf>,
+
+    /// Return the lexicographically smallest solution, reading the cells in row-major order with
+    /// digits 1-9, instead of the first one the SAT solver finds. The result then only depends on
+    /// the grid and the constraints, so it is the same across runs and versions of the solver.
+    /// Grids with a unique solution are not affected, but solving takes longer.
+    pub canonical: bool,
+
+    /// Break the symmetry between the digits that are not among the clues. Swapping two such
+    /// digits throughout a solution gives another solution, so the solutions fall into groups of
+    /// `k!` for `k` unused digits. With this set only one solution of every group is admitted:
+    /// the one in which those digits first appear in increasing order, reading the cells in
+    /// row-major order. Counting solutions then counts the groups. No other symmetry, such as
+    /// reordering rows or transposing the grid, is broken. Only valid when the constraints do not
+    /// depend on the values of the digits, so it cannot be combined with `parity`,
+    /// `thermometers`, `edge_clues`, `candidates` or sum and product cages.
+    pub break_digit_symmetry: bool,
+}
+
+impl Default for SolveOptions {
+    fn default() -> Self {
+        Self {
+            enforce_rows: true,
+            enforce_columns: true,
+            enforce_boxes: true,
+            preprocess: false,
+            encoding: Encoding::Pairwise,
+            parity: Vec::new(),
+            disjoint_groups: false,
+            extra_regions: Vec::new(),
+            thermometers: Vec::new(),
+            clones: Vec::new(),
+            cages: Vec::new(),
+            edge_clues: Vec::new(),
+            candidates: None,
+            verify: false,
+            proof: None,
+            canonical: false,
+            break_digit_symmetry: false,
+        }
+    }
+}
+
+impl SolveOptions {
+    /// Returns whether the options add no constraints on top of the classic sudoku rules, and
+    /// remove none of them.
+    fn is_classic(&self) -> bool {
+        self.enforce_rows
+            && self.enforce_columns
+            && self.enforce_boxes
+            && self.parity.is_empty()
+            && !self.disjoint_groups
+            && self.extra_regions.is_empty()
+            && self.thermometers.is_empty()
+            && self.clones.is_empty()
+            && self.cages.is_empty()
+            && self.edge_clues.is_empty()
+            && self.candidates.is_none()
+    }
+
+    /// Returns whether the grid is completely filled in and every enforced row, column and box
+    /// contains each digit exactly once, see [`is_valid_solution`].
+    fn satisfies_rules(&self, grid: &Grid) -> bool {
+        grid.is_complete()
+            && (0..9).all(|i| {
+                (!self.enforce_rows || has_every_digit(grid.row(i)))
+                    && (!self.enforce_columns || has_every_digit(grid.col(i)))
+                    && (!self.enforce_boxes || has_every_digit(grid.box_cells(i)))
+            })
+    }
+
+    /// Checks that all constraints are well-formed.
+    fn validate(&self) -> Result<(), SolveError> {
+        if let Some(&(x, y, _)) = self.parity.iter().find(|&&(x, y, _)| x >= 9 || y >= 9) {
+            return Err(SolveError::InvalidConstraint(format!(
+                "parity cell ({x}, {y}) is out of bounds"
+            )));
+        }
+        for region in &self.extra_regions {
+            region.validate()?;
+        }
+        for thermometer in &self.thermometers {
+            thermometer.validate()?;
+        }
+        for clone in &self.clones {
+            clone.validate()?;
+        }
+        for cage in &self.cages {
+            cage.validate()?;
+        }
+        for clue in &self.edge_clues {
+            clue.validate()?;
+        }
+        let digit_dependent = !self.parity.is_empty()
+            || !self.thermometers.is_empty()
+            || !self.edge_clues.is_empty()
+            || self.candidates.is_some()
+            || self
+                .cages
+                .iter()
+                .any(|cage| cage.constraint != RegionConstraint::Unique);
+        if self.break_digit_symmetry && digit_dependent {
+            return Err(SolveError::InvalidConstraint(
+                "digit symmetry cannot be broken with constraints that depend on the digits"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The solutions collected by [`Grid::solutions`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Solutions {
+    /// The solutions, at most as many as the limit.
+    pub grids: Vec<Grid>,
+    /// Whether the grid has more solutions than the limit, so `grids` is not all of them.
+    pub truncated: bool,
+}
+
+/// The per cell entropies computed by [`Grid::cell_entropy`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellEntropy {
+    /// The entropy of every cell in row-major order, in bits.
+    pub entropy: [f32; 81],
+    /// Whether the grid has more solutions than were sampled, so `entropy` may understate how
+    /// ambiguous the cells are.
+    pub truncated: bool,
+}
+
+/// Statistics gathered while solving a grid.
+#[derive(Clone, Debug, Default)]
+pub struct SolveStats {
+    /// Number of cells whose value was known after preprocessing, including the filled in cells.
+    /// Zero if preprocessing was disabled.
+    pub preprocessed_cells: usize,
+
+    /// Time spent in the SAT solver, excluding the construction of the formula.
+    pub solve_time: Duration,
+
+    /// Time spent constructing the formula and loading it into the solver.
+    pub formula_time: Duration,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid {
+    cells: [Cell; 81],
+}
+
+/// Number of variables that encode the cells, see [`var_for`]. Constraints such as cages may add
+/// auxiliary variables after these.
+pub const NUM_VARS: usize = 729;
+
+/// Returns the variable that is true iff cell `(x, y)` contains the (0-based) digit `d`. The
+/// variables are laid out by row, then column, then digit: `y * 81 + x * 9 + d`, so the formula
+/// for a grid uses variables `0..NUM_VARS` for its cells.
+pub fn var_for(x: usize, y: usize, d: usize) -> Var {
+    Var::from_index(y * 81 + x * 9 + d)
+}
+
+/// Returns the cell `(x, y)` and digit `d` that `var` stands for, the inverse of [`var_for`].
+/// `var` must be one of the first [`NUM_VARS`] variables.
+pub fn cell_for(var: Var) -> (usize, usize, usize) {
+    let index = var.index();
+    debug_assert!(index < NUM_VARS, "{var} does not encode a cell");
+    ((index % 81) / 9, index / 81, index % 9)
+}
+
+/// Returns the index of the box containing cell `(x, y)`. Boxes are numbered in row-major order,
+/// so box 0 is the top-left box and box 8 the bottom-right one.
+pub fn box_index(x: usize, y: usize) -> usize {
+    (y / 3) * 3 + x / 3
+}
+
+/// Returns the coordinates `(x, y)` of the cells of box `box_idx` in row-major order.
+pub fn box_cells(box_idx: usize) -> [(usize, usize); 9] {
+    std::array::from_fn(|i| ((box_idx % 3) * 3 + i % 3, (box_idx / 3) * 3 + i / 3))
+}
+
+/// Adds the clauses of the sudoku rules to `formula`, which can be a solver directly so that no
+/// intermediate formula has to be built.
+fn add_sudoku_rules(formula: &mut impl ExtendFormula) {
+    add_rules(formula, &SolveOptions::default());
+}
+
+/// Adds the rules of sudoku, leaving out the rows, columns or boxes that `options` does not
+/// enforce. Every cell always gets exactly one digit.
+fn add_rules(formula: &mut impl ExtendFormula, options: &SolveOptions) {
+    if options.encoding == Encoding::Sequential {
+        add_rules_sequential(formula, options);
+        return;
+    }
+
+    for y in 0..9 {
+        for x in 0..9 {
+            // Only one value per cell
+            for a in 0..9 {
+                let v_a = var_for(x, y, a);
+                for b in (a + 1)..9 {
+                    let v_b = var_for(x, y, b);
+                    formula.add_clause(&[v_a.negative(), v_b.negative()]);
+                }
+            }
+
+            // Each cell must contain at least one value.
+            let select_at_least_one_clause = (0..9)
+                .map(|v| var_for(x, y, v).positive())
+                .collect::<Vec<_>>();
+            formula.add_clause(&select_at_least_one_clause);
+        }
+    }
+
+    // For each row
+    for y in (0..9).filter(|_| options.enforce_rows) {
+        for x in 0..9 {
+            for d in 0..9 {
+                for x2 in 0..9 {
+                    if x != x2 {
+                        formula.add_clause(&[
+                            var_for(x, y, d).negative(),
+                            var_for(x2, y, d).negative(),
+                        ])
+                    }
+                }
+            }
+        }
+    }
+
+    // For each column
+    for x in (0..9).filter(|_| options.enforce_columns) {
+        for y in 0..9 {
+            for d in 0..9 {
+                for y2 in 0..9 {
+                    if y != y2 {
+                        formula.add_clause(&[
+                            var_for(x, y, d).negative(),
+                            var_for(x, y2, d).negative(),
+                        ])
+                    }
+                }
+            }
+        }
+    }
+
+    // For each block
+    for block_idx in (0..9).filter(|_| options.enforce_boxes) {
+        let cells = box_cells(block_idx);
+        for &(x1, y1) in &cells {
+            for d in 0..9 {
+                for &(x2, y2) in &cells {
+                    if (x1, y1) != (x2, y2) {
+                        formula.add_clause(&[
+                            var_for(x1, y1, d).negative(),
+                            var_for(x2, y2, d).negative(),
+                        ])
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Adds the rules of [`add_rules`] with the at most one constraints in the
+/// [`Encoding::Sequential`] encoding.
+fn add_rules_sequential(formula: &mut impl ExtendFormula, options: &SolveOptions) {
+    // The cell variables come first, so the auxiliary variables are allocated after them
+    for y in 0..9 {
+        for x in 0..9 {
+            let lits = (0..9)
+                .map(|d| var_for(x, y, d).positive())
+                .collect::<Vec<_>>();
+            formula.add_clause(&lits);
+        }
+    }
+    for y in 0..9 {
+        for x in 0..9 {
+            let lits = (0..9).map(|d| var_for(x, y, d).positive());
+            add_at_most_one_sequential(formula, &lits.collect::<Vec<_>>());
+        }
+    }
+
+    let rows = (0..9)
+        .filter(|_| options.enforce_rows)
+        .map(|y| std::array::from_fn::<_, 9, _>(|x| (x, y)));
+    let columns = (0..9)
+        .filter(|_| options.enforce_columns)
+        .map(|x| std::array::from_fn(|y| (x, y)));
+    let boxes = (0..9).filter(|_| options.enforce_boxes).map(box_cells);
+    for cells in rows.chain(columns).chain(boxes) {
+        for d in 0..9 {
+            let lits = cells.iter().map(|&(x, y)| var_for(x, y, d).positive());
+            add_at_most_one_sequential(formula, &lits.collect::<Vec<_>>());
+        }
+    }
+}
+
+/// Adds the sequential counter encoding of "at most one of `lits` is true". Auxiliary variable
+/// `s[i]` is implied when one of the first `i + 1` literals is true, and then forbids the next
+/// literal.
+fn add_at_most_one_sequential(formula: &mut impl ExtendFormula, lits: &[Lit]) {
+    let n = lits.len();
+    if n < 2 {
+        return;
+    }
+    let s = (0..n - 1).map(|_| formula.new_var()).collect::<Vec<_>>();
+    formula.add_clause(&[!lits[0], s[0].positive()]);
+    for i in 1..n - 1 {
+        formula.add_clause(&[!lits[i], s[i].positive()]);
+        formula.add_clause(&[s[i - 1].negative(), s[i].positive()]);
+        formula.add_clause(&[!lits[i], s[i - 1].negative()]);
+    }
+    formula.add_clause(&[!lits[n - 1], s[n - 2].negative()]);
+}
+
+/// Repeatedly assigns the remaining literal of clauses in which all other literals are false.
+/// Returns the value of every variable that is implied by the formula this way, or `None` if
+/// propagation runs into a clause with only false literals.
+fn propagate(formula: &CnfFormula) -> Option<Vec<Option<bool>>> {
+    let mut assignment = vec![None; formula.var_count()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        'clauses: for clause in formula.iter() {
+            let mut unassigned = None;
+            for &lit in clause {
+                match assignment[lit.index()] {
+                    Some(value) if value == lit.is_positive() => continue 'clauses,
+                    Some(_) => {}
+                    None if unassigned.is_some() => continue 'clauses,
+                    None => unassigned = Some(lit),
+                }
+            }
+            let lit = unassigned?;
+            assignment[lit.index()] = Some(lit.is_positive());
+            changed = true;
+        }
+    }
+    Some(assignment)
+}
+
+/// Maximum number of search nodes visited by [`Grid::count_solutions_exact`].
+const EXACT_COUNT_NODE_LIMIT: u64 = 1_000_000;
+
+/// Digits still allowed in rows, columns and boxes, as bit masks, for the exact counter.
+struct CountState {
+    cells: [Cell; 81],
+    rows: [u16; 9],
+    columns: [u16; 9],
+    boxes: [u16; 9],
+    nodes: u64,
+}
+
+impl CountState {
+    fn toggle(&mut self, i: usize, d: u8) {
+        let (x, y) = (i % 9, i / 9);
+        self.rows[y] ^= 1 << d;
+        self.columns[x] ^= 1 << d;
+        self.boxes[box_index(x, y)] ^= 1 << d;
+    }
+
+    fn allowed(&self, i: usize) -> u16 {
+        let (x, y) = (i % 9, i / 9);
+        !(self.rows[y] | self.columns[x] | self.boxes[box_index(x, y)]) & 0x1ff
+    }
+
+    /// Counts the completions by branching on the empty cell with the fewest allowed digits.
+    /// Returns `None` once the node limit is exceeded.
+    fn count(&mut self) -> Option<u64> {
+        self.nodes += 1;
+        if self.nodes > EXACT_COUNT_NODE_LIMIT {
+            return None;
+        }
+
+        let Some(i) = (0..81)
+            .filter(|&i| self.cells[i].is_none())
+            .min_by_key(|&i| self.allowed(i).count_ones())
+        else {
+            return Some(1);
+        };
+
+        let allowed = self.allowed(i);
+        let mut count = 0;
+        for d in (0..9u8).filter(|&d| allowed & (1 << d) != 0) {
+            self.cells[i] = Some(d);
+            self.toggle(i, d);
+            let sub = self.count();
+            self.toggle(i, d);
+            self.cells[i] = None;
+            count += sub?;
+        }
+        Some(count)
+    }
+}
+
+/// Runs the solver. Solving only fails when a proof cannot be written or a proof processor
+/// aborts, which is reported as an inconsistency outside of [`Grid::solve_with`].
+fn run_solver(solver: &mut Solver) -> Result<bool, SolveError> {
+    solver
+        .solve()
+        .map_err(|e| SolveError::InternalInconsistency(e.to_string()))
+}
+
+/// Returns the model found by the last successful call to [`run_solver`].
+fn last_model(solver: &Solver) -> Result<Vec<Lit>, SolveError> {
+    solver.model().ok_or_else(|| {
+        SolveError::InternalInconsistency("the solver did not produce a model".to_string())
+    })
+}
+
+/// Counts the models of the formula loaded in the solver, stopping once `limit` models have been
+/// found. Models are distinguished by their cell values only.
+fn count_models(solver: &mut Solver, limit: usize) -> Result<usize, SolveError> {
+    let mut count = 0;
+    while count < limit && run_solver(solver)? {
+        count += 1;
+
+        // Block the solution that was just found
+        let blocking_clause = last_model(solver)?
+            .into_iter()
+            .filter(|lit| lit.is_positive() && lit.index() < NUM_VARS)
+            .map(|lit| !lit)
+            .collect::<Vec<_>>();
+        solver.add_clause(&blocking_clause);
+    }
+    Ok(count)
+}
+
+impl Grid {
+    /// The character that stands for an empty cell in the line format of [`Grid::from_str`] and
+    /// [`Grid::to_line`], and in the default [`Display`] of a grid.
+    pub const EMPTY: char = ' ';
+
+    /// Returns a grid without any filled in cells.
+    pub fn empty() -> Grid {
+        Grid { cells: [None; 81] }
+    }
+
+    /// Returns the grid with (0-based) digit `d` filled in at `(x, y)`, for building grids in a
+    /// single expression. Panics if the cell or digit is out of range.
+    pub fn with_clue(mut self, x: usize, y: usize, d: u8) -> Self {
+        assert!(d < 9, "digit {d} is out of range");
+        self[(x, y)] = Some(d);
+        self
+    }
+
+    /// Builds a grid from the output of digit recognition: `cells[y][x]` is the digit 1-9
+    /// detected in cell `(x, y)`, if any, like in [`Grid::from_array`], and `confidence[y][x]` how
+    /// certain the detection is. Detections with a confidence below `threshold` are left empty.
+    /// A detected 0 is an error rather than an empty cell; an empty cell is `None`.
+    pub fn from_ocr(
+        cells: [[Option<u8>; 9]; 9],
+        confidence: [[f32; 9]; 9],
+        threshold: f32,
+    ) -> Result<Grid, String> {
+        let mut grid = Grid::empty();
+        for y in 0..9 {
+            for x in 0..9 {
+                match cells[y][x] {
+                    Some(value @ 1..=9) if confidence[y][x] >= threshold => {
+                        grid.cells[y * 9 + x] = Some(value - 1);
+                    }
+                    Some(value @ (0 | 10..)) => {
+                        return Err(format!("invalid cell value {value} at ({x}, {y})"));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(grid)
+    }
+
+    /// Generates a random completely filled in grid. The same seed always produces the same grid.
+    ///
+    /// # Panics
+    ///
+    /// Panics if varisat fails, which it only does when writing a proof, and never does for the
+    /// solver used here.
+    #[cfg_attr(feature = "tracing", tracing::instrument)]
+    pub fn generate_filled(seed: u64) -> Grid {
+        let mut rng = Rng::new(seed);
+        let mut order = (0..81).collect::<Vec<_>>();
+        rng.shuffle(&mut order);
+
+        // Visit the cells in a random order and give each a random digit that still allows the
+        // grid to be completed.
+        let mut solver = Grid::empty().solver();
+        let mut assumptions = Vec::with_capacity(81);
+        for i in order {
+            let mut digits = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+            rng.shuffle(&mut digits);
+            for d in digits {
+                assumptions.push(var_for(i % 9, i / 9, d).positive());
+                solver.assume(&assumptions);
+                if run_solver(&mut solver).expect("varisat does not fail without a proof") {
+                    break;
+                }
+                assumptions.pop();
+            }
+        }
+
+        let grid = last_model(&solver)
+            .and_then(|model| Grid::empty().apply_model(&model))
+            .expect("the empty grid can be completed");
+        debug!("generated filled grid from seed {seed}");
+        grid
+    }
+
+    /// Generates a random completely filled in grid, seeded from system entropy. Unlike
+    /// [`Grid::generate_filled`] the result differs between calls and cannot be reproduced.
+    pub fn generate_filled_random() -> Grid {
+        let seed = RandomState::new().build_hasher().finish();
+        Grid::generate_filled(seed)
+    }
+
+    /// Fills in `n` random empty cells (determined by `seed`) with random digits that do not
+    /// conflict with the row, column or box of the cell. The result need not be solvable. Returns
+    /// `None` if fewer than `n` cells can be filled in that way.
+    pub fn fill_random_consistent(&self, n: usize, seed: u64) -> Option<Grid> {
+        let mut rng = Rng::new(seed);
+        let mut order = (0..81)
+            .filter(|&i| self.cells[i].is_none())
+            .collect::<Vec<_>>();
+        rng.shuffle(&mut order);
+
+        let mut grid = self.clone();
+        let mut placed = 0;
+        for i in order {
+            if placed == n {
+                break;
+            }
+            let candidates = grid.candidates()[i];
+            let digits = (0..9u8)
+                .filter(|&d| candidates[d as usize])
+                .collect::<Vec<_>>();
+            if !digits.is_empty() {
+                grid.cells[i] = Some(digits[rng.below(digits.len())]);
+                placed += 1;
+            }
+        }
+
+        debug!("placed {placed} of {n} random clues");
+        (placed == n).then_some(grid)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Cell {
+        self.cells[y * 9 + x]
+    }
+
+    /// Returns the cells of row `y`, from left to right.
+    pub fn row(&self, y: usize) -> [Cell; 9] {
+        std::array::from_fn(|x| self.get(x, y))
+    }
+
+    /// Returns the cells of column `x`, from top to bottom.
+    pub fn col(&self, x: usize) -> [Cell; 9] {
+        std::array::from_fn(|y| self.get(x, y))
+    }
+
+    /// Returns the cells of box `box_idx` in row-major order. Boxes are numbered in row-major
+    /// order as well, so box 0 is the top-left box and box 8 the bottom-right one.
+    pub fn box_cells(&self, box_idx: usize) -> [Cell; 9] {
+        box_cells(box_idx).map(|(x, y)| self.get(x, y))
+    }
+
+    /// Returns the candidates of every cell in row-major order: the digits that do not already
+    /// appear in the row, column or box of the cell. A filled in cell only has its own digit as
+    /// candidate.
+    pub fn candidates(&self) -> CandidateGrid {
+        let mut candidates = [[true; 9]; 81];
+        for y in 0..9 {
+            for x in 0..9 {
+                let cell = &mut candidates[y * 9 + x];
+                if let Some(d) = self.get(x, y) {
+                    *cell = [false; 9];
+                    cell[d as usize] = true;
+                    continue;
+                }
+                let box_peers = box_cells(box_index(x, y));
+                for (i, (bx, by)) in box_peers.into_iter().enumerate() {
+                    let peers = [self.get(i, y), self.get(x, i), self.get(bx, by)];
+                    for d in peers.into_iter().flatten() {
+                        cell[d as usize] = false;
+                    }
+                }
+            }
+        }
+        candidates
+    }
+
+    /// Checks pencil marks kept by a player, given per cell in row-major order like
+    /// [`Grid::candidates`], and returns the empty cells whose marks are wrong, in row-major
+    /// order. Marks are wrong if they include a digit that already appears in the row, column or
+    /// box of the cell, or if the grid has a unique solution and they leave out its digit.
+    /// Leaving out other digits is fine, the player may have ruled them out by other means. The
+    /// marks of filled in cells are ignored.
+    pub fn check_pencil_marks(
+        &self,
+        marks: &CandidateGrid,
+    ) -> Result<Vec<(usize, usize)>, SolveError> {
+        let candidates = self.candidates();
+        let solutions = self.solutions(1)?;
+        let solution = solutions.grids.first().filter(|_| !solutions.truncated);
+        Ok(self
+            .empty_cells()
+            .filter(|&(x, y)| {
+                let i = y * 9 + x;
+                let eliminated = (0..9).any(|d| marks[i][d] && !candidates[i][d]);
+                let missing = solution
+                    .and_then(|solution| solution.get(x, y))
+                    .is_some_and(|d| !marks[i][d as usize]);
+                eliminated || missing
+            })
+            .collect())
+    }
+
+    /// Returns how many empty cells have 0, 1, ..., 9 candidates after unit propagation of the
+    /// formula, a cheap indication of difficulty: cells with many candidates make a puzzle
+    /// harder. If propagation runs into a contradiction the candidates of [`Grid::candidates`]
+    /// are counted instead.
+    pub fn candidate_histogram(&self) -> [usize; 10] {
+        let candidates = self.candidates();
+        let assignment = propagate(&self.formula(&SolveOptions::default()));
+        let mut histogram = [0; 10];
+        for (x, y) in self.empty_cells() {
+            let count = match &assignment {
+                Some(assignment) => (0..9)
+                    .filter(|&d| assignment[var_for(x, y, d).index()] != Some(false))
+                    .count(),
+                None => candidates[y * 9 + x].iter().filter(|&&c| c).count(),
+            };
+            histogram[count] += 1;
+        }
+        histogram
+    }
+
+    /// Returns the empty cell with the fewest candidates, preferring the first one in row-major
+    /// order if there are several. Returns `None` if the grid is completely filled in.
+    pub fn most_constrained_cell(&self) -> Option<(usize, usize)> {
+        let candidates = self.candidates();
+        (0..81)
+            .filter(|&i| self.cells[i].is_none())
+            .min_by_key(|&i| candidates[i].iter().filter(|&&c| c).count())
+            .map(|i| (i % 9, i / 9))
+    }
+
+    /// Returns true if the cell at `(x, y)` does not contain a digit.
+    pub fn is_empty_cell(&self, x: usize, y: usize) -> bool {
+        self.get(x, y).is_none()
+    }
+
+    /// Iterates over all 81 cells in row-major order, yielding `((x, y), cell)`.
+    pub fn cells(&self) -> impl Iterator<Item = ((usize, usize), Cell)> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(i, &cell)| ((i % 9, i / 9), cell))
+    }
+
+    /// Iterates over the filled in cells in row-major order, yielding `(x, y, digit)`.
+    pub fn filled_cells(&self) -> impl Iterator<Item = (usize, usize, u8)> + '_ {
+        self.cells()
+            .filter_map(|((x, y), cell)| cell.map(|d| (x, y, d)))
+    }
+
+    /// Returns the number of filled in cells.
+    pub fn clue_count(&self) -> usize {
+        self.cells.iter().filter(|c| c.is_some()).count()
+    }
+
+    /// Returns the number of empty cells.
+    pub fn num_empty(&self) -> usize {
+        81 - self.clue_count()
+    }
+
+    /// Iterates over the empty cells `(x, y)` in row-major order.
+    pub fn empty_cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.cells()
+            .filter_map(|(position, cell)| cell.is_none().then_some(position))
+    }
+
+    /// Returns true if every cell contains a digit.
+    pub fn is_complete(&self) -> bool {
+        self.cells.iter().all(|c| c.is_some())
+    }
+
+    /// Returns the number of filled in cells in each box, with the boxes in row-major order.
+    pub fn box_clue_counts(&self) -> [usize; 9] {
+        std::array::from_fn(|b| self.box_cells(b).iter().flatten().count())
+    }
+
+    /// Returns the number of filled in cells in each band, the three rows of boxes from top to
+    /// bottom.
+    pub fn band_clue_counts(&self) -> [usize; 3] {
+        let boxes = self.box_clue_counts();
+        std::array::from_fn(|band| boxes[band * 3..band * 3 + 3].iter().sum())
+    }
+
+    /// Returns the number of filled in cells in each stack, the three columns of boxes from left
+    /// to right.
+    pub fn stack_clue_counts(&self) -> [usize; 3] {
+        let boxes = self.box_clue_counts();
+        std::array::from_fn(|stack| boxes.iter().skip(stack).step_by(3).sum())
+    }
+
+    /// Returns the grid as a single line of 81 characters in row-major order, using `1`-`9` for
+    /// the digits and [`Grid::EMPTY`] for empty cells. This is the format accepted by
+    /// [`Grid::from_str`].
+    pub fn to_line(&self) -> String {
+        self.cells
+            .iter()
+            .map(|cell| match cell {
+                Some(d) => Alphabet::DIGITS.symbol(*d),
+                None => Grid::EMPTY,
+            })
+            .collect()
+    }
+
+    /// Returns the grid as a 9x9 array of rows of digits 1-9, with 0 for empty cells, for numeric
+    /// code. For example, `ndarray::arr2(&grid.to_array())` turns it into an `ndarray::Array2`.
+    pub fn to_array(&self) -> [[u8; 9]; 9] {
+        std::array::from_fn(|y| self.row(y).map(|cell| cell.map_or(0, |d| d + 1)))
+    }
+
+    /// Builds a grid from rows of digits 1-9 and 0 for empty cells, the inverse of
+    /// [`Grid::to_array`].
+    pub fn from_array(rows: &[[u8; 9]; 9]) -> Result<Grid, String> {
+        let mut grid = Grid::empty();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &value) in row.iter().enumerate() {
+                grid[(x, y)] = match value {
+                    0 => None,
+                    1..=9 => Some(value - 1),
+                    _ => return Err(format!("invalid cell value {value} at ({x}, {y})")),
+                };
+            }
+        }
+        Ok(grid)
+    }
+
+    /// Returns the clues as `(x, y, digit)` triples in row-major order, with digits 1-9 as in
+    /// [`Grid::to_array`]. Puzzles with few clues are smaller this way than as a full grid.
+    pub fn to_coords(&self) -> Vec<(u8, u8, u8)> {
+        self.filled_cells()
+            .map(|(x, y, d)| (x as u8, y as u8, d + 1))
+            .collect()
+    }
+
+    /// Builds a grid from `(x, y, digit)` triples with digits 1-9, the inverse of
+    /// [`Grid::to_coords`]. The triples may come in any order, but a cell cannot be given two
+    /// different digits.
+    pub fn from_coords(coords: &[(u8, u8, u8)]) -> Result<Grid, String> {
+        let mut grid = Grid::empty();
+        for &(x, y, value) in coords {
+            if x >= 9 || y >= 9 {
+                return Err(format!("cell ({x}, {y}) is outside the grid"));
+            }
+            if !(1..=9).contains(&value) {
+                return Err(format!("invalid cell value {value} at ({x}, {y})"));
+            }
+            let cell = &mut grid[(x as usize, y as usize)];
+            if cell.is_some_and(|d| d != value - 1) {
+                return Err(format!("cell ({x}, {y}) is given more than one digit"));
+            }
+            *cell = Some(value - 1);
+        }
+        Ok(grid)
+    }
+
+    /// Places digit `d` in the empty cell `(x, y)`, unless that conflicts with a digit in the same
+    /// row, column or box. Cells that already contain a digit, givens or earlier moves, cannot be
+    /// overwritten.
+    pub fn apply_move(&mut self, x: usize, y: usize, d: u8) -> Result<(), MoveError> {
+        if x >= 9 || y >= 9 {
+            return Err(MoveError::OutOfBounds);
+        }
+        if d >= 9 {
+            return Err(MoveError::InvalidDigit);
+        }
+        if self.get(x, y).is_some() {
+            return Err(MoveError::Given);
+        }
+
+        for unit in [Unit::Row(y), Unit::Column(x), Unit::Box(box_index(x, y))] {
+            if let Some((cx, cy)) = unit
+                .cells()
+                .into_iter()
+                .find(|&(cx, cy)| self.get(cx, cy) == Some(d))
+            {
+                return Err(MoveError::Conflict { x: cx, y: cy });
+            }
+        }
+
+        self.cells[y * 9 + x] = Some(d);
+        Ok(())
+    }
+
+    /// Returns every pair of filled in cells `((x, y), (x, y))` that hold the same digit and share
+    /// a row, column or box. The first cell of each pair comes first in row-major order.
+    pub fn conflicts(&self) -> Vec<((usize, usize), (usize, usize))> {
+        let filled = self.filled_cells().collect::<Vec<_>>();
+        let mut conflicts = Vec::new();
+        for (i, &(x1, y1, d1)) in filled.iter().enumerate() {
+            for &(x2, y2, d2) in &filled[i + 1..] {
+                let same_box = box_index(x1, y1) == box_index(x2, y2);
+                if d1 == d2 && (x1 == x2 || y1 == y2 || same_box) {
+                    conflicts.push(((x1, y1), (x2, y2)));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Checks that no digit appears twice in a row, column or box. Otherwise returns one
+    /// [`ClueConflict`] per unit and digit, with the rows first, then the columns and then the
+    /// boxes, as in [`Unit::all`]. Unlike [`Grid::conflicts`] this tells which unit is violated, so
+    /// a conflict in both a row and a box is reported twice.
+    pub fn validate_clues(&self) -> Result<(), Vec<ClueConflict>> {
+        let mut conflicts = Vec::new();
+        for unit in Unit::all() {
+            for digit in 0..9 {
+                let cells = unit
+                    .cells()
+                    .into_iter()
+                    .filter(|&(x, y)| self.get(x, y) == Some(digit))
+                    .collect::<Vec<_>>();
+                if cells.len() > 1 {
+                    conflicts.push(ClueConflict { cells, digit, unit });
+                }
+            }
+        }
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(conflicts)
+        }
+    }
+
+    /// Returns the number of cells that differ between the grids: the number of clues that have to
+    /// be added, removed or changed to turn one grid into the other.
+    pub fn edit_distance(&self, other: &Grid) -> usize {
+        self.cells
+            .iter()
+            .zip(&other.cells)
+            .filter(|(a, b)| a != b)
+            .count()
+    }
+
+    /// Returns a copy of the grid in which only the given cells `(x, y)` are kept and all others
+    /// are cleared.
+    pub fn keep_only(&self, keep: &[(usize, usize)]) -> Grid {
+        let mut result = Grid::empty();
+        for &(x, y) in keep {
+            result.cells[y * 9 + x] = self.get(x, y);
+        }
+        result
+    }
+
+    /// Combines the filled in cells of both grids. Fails if the grids contain different digits
+    /// in the same cell.
+    pub fn overlay(&self, other: &Grid) -> Result<Grid, String> {
+        let mut result = self.clone();
+        for y in 0..9 {
+            for x in 0..9 {
+                match (self.get(x, y), other.get(x, y)) {
+                    (Some(a), Some(b)) if a != b => {
+                        return Err(format!(
+                            "conflicting digits at ({x}, {y}): '{}' and '{}'",
+                            a + 1,
+                            b + 1
+                        ))
+                    }
+                    (None, cell) => result.cells[y * 9 + x] = cell,
+                    _ => {}
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Constructs the formula for this grid: the sudoku rules, the filled in values and the extra
+    /// constraints from the options.
+    fn formula(&self, options: &SolveOptions) -> CnfFormula {
+        let mut formula = CnfFormula::new();
+        self.add_formula_to(&mut formula, options);
+        formula
+    }
+
+    /// Adds the clauses of [`Grid::formula`] to `formula`, which can be a solver directly.
+    fn add_formula_to(&self, formula: &mut impl ExtendFormula, options: &SolveOptions) {
+        add_rules(formula, options);
+
+        // Add filled in values
+        for (x, y, d) in self.filled_cells() {
+            let v = var_for(x, y, d as usize);
+            formula.add_clause(&[v.positive()]);
+        }
+
+        // For each extra region
+        let disjoint_groups = options
+            .disjoint_groups
+            .then(|| (0..9).map(ExtraRegion::disjoint_group))
+            .into_iter()
+            .flatten();
+        for region in options.extra_regions.iter().cloned().chain(disjoint_groups) {
+            for d in 0..9 {
+                for (i, &(x1, y1)) in region.cells.iter().enumerate() {
+                    for &(x2, y2) in &region.cells[i + 1..] {
+                        formula.add_clause(&[
+                            var_for(x1, y1, d).negative(),
+                            var_for(x2, y2, d).negative(),
+                        ]);
+                    }
+                }
+            }
+        }
+
+        // Forbid the digits of the wrong parity
+        for &(x, y, parity) in &options.parity {
+            for d in (0..9).filter(|&d| !parity.allows(d)) {
+                formula.add_clause(&[var_for(x, y, d as usize).negative()]);
+            }
+        }
+
+        // Digits must increase along every thermometer
+        for thermometer in &options.thermometers {
+            for pair in thermometer.0.windows(2) {
+                let [(x1, y1), (x2, y2)] = [pair[0], pair[1]];
+                for d1 in 0..9 {
+                    for d2 in 0..=d1 {
+                        formula.add_clause(&[
+                            var_for(x1, y1, d1).negative(),
+                            var_for(x2, y2, d2).negative(),
+                        ]);
+                    }
+                }
+            }
+        }
+
+        // Cloned cells contain the same digit
+        for clone in &options.clones {
+            for (&(x1, y1), &(x2, y2)) in clone.0.iter().zip(&clone.1) {
+                for d in 0..9 {
+                    let (a, b) = (var_for(x1, y1, d), var_for(x2, y2, d));
+                    formula.add_clause(&[a.negative(), b.positive()]);
+                    formula.add_clause(&[b.negative(), a.positive()]);
+                }
+            }
+        }
+
+        for cage in &options.cages {
+            cage.add_to(formula);
+            if let RegionConstraint::Sum(sum) = cage.constraint {
+                let unique = options.cages.iter().any(|other| {
+                    other.constraint == RegionConstraint::Unique && other.same_cells(cage)
+                });
+                if unique {
+                    cage.add_killer_digits(formula, sum);
+                }
+            }
+        }
+
+        for clue in &options.edge_clues {
+            clue.add_to(formula);
+        }
+
+        // Rule out the digits that are not a candidate
+        if let Some(candidates) = &options.candidates {
+            for (i, cell) in candidates.iter().enumerate() {
+                for d in (0..9).filter(|&d| !cell[d]) {
+                    formula.add_clause(&[var_for(i % 9, i / 9, d).negative()]);
+                }
+            }
+        }
+
+        if options.break_digit_symmetry {
+            self.add_digit_symmetry_breaking(formula);
+        }
+    }
+
+    /// Constructs a solver loaded with the sudoku rules and the filled in values of this grid.
+    fn solver(&self) -> Solver<'static> {
+        self.solver_with(&SolveOptions::default())
+    }
+
+    /// Adds the clauses of [`SolveOptions::break_digit_symmetry`]: for consecutive unused digits
+    /// `a < b`, a cell can only hold `b` if one of the cells before it holds `a`.
+    fn add_digit_symmetry_breaking(&self, formula: &mut impl ExtendFormula) {
+        let mut used = [false; 9];
+        for (_, _, d) in self.filled_cells() {
+            used[d as usize] = true;
+        }
+        let unused = (0..9).filter(|&d| !used[d]).collect::<Vec<_>>();
+        for pair in unused.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            for i in 0..81 {
+                let mut clause = vec![var_for(i % 9, i / 9, b).negative()];
+                clause.extend((0..i).map(|j| var_for(j % 9, j / 9, a).positive()));
+                formula.add_clause(&clause);
+            }
+        }
+    }
+
+    /// Constructs a solver loaded with the formula for this grid and the given options.
+    fn solver_with(&self, options: &SolveOptions) -> Solver<'static> {
+        let mut solver = Solver::new();
+        self.add_formula_to(&mut solver, options);
+        solver
+    }
+
+    /// Fills in the values of the given model. Fails if the model contradicts a filled in cell or
+    /// puts two digits in a cell, which indicates a bug in the encoding.
+    fn apply_model(mut self, model: &[Lit]) -> Result<Grid, SolveError> {
+        for lit in model {
+            // Skip the auxiliary variables of the cages
+            if lit.is_positive() && lit.index() < NUM_VARS {
+                let (x, y, d) = cell_for(lit.var());
+                match self.cells[y * 9 + x].replace(d as u8) {
+                    Some(prev) if prev as usize != d => {
+                        return Err(SolveError::InternalInconsistency(format!(
+                            "the model puts both {} and {} in cell ({x}, {y})",
+                            prev + 1,
+                            d + 1
+                        )));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// Solves the grid with varisat. See [`Grid::solve_using`] for other backends.
+    pub fn solve(self) -> Result<Grid, SolveError> {
+        self.solve_with(&SolveOptions::default())
+            .map(|(solution, _)| solution)
+    }
+
+    /// Solves the grid on tokio's blocking thread pool, so hard puzzles do not stall the async
+    /// runtime. Must be called from within a tokio runtime; panics otherwise.
+    #[cfg(feature = "async")]
+    pub fn solve_async(self) -> impl std::future::Future<Output = Result<Grid, SolveError>> {
+        let task = tokio::task::spawn_blocking(move || self.solve());
+        async move {
+            match task.await {
+                Ok(result) => result,
+                Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+                Err(e) => Err(SolveError::InternalInconsistency(format!(
+                    "solve task did not complete: {e}"
+                ))),
+            }
+        }
+    }
+
+    /// Solves the grid using the given options and reports statistics about the solve.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(clues = self.clue_count(), satisfiable))
+    )]
+    pub fn solve_with(&self, options: &SolveOptions) -> Result<(Grid, SolveStats), SolveError> {
+        debug!("solving grid with {} clues", self.clue_count());
+        options.validate()?;
+
+        // A completely filled in grid is either solved already or cannot be solved at all
+        if self.is_complete() && options.proof.is_none() {
+            if !options.satisfies_rules(self) {
+                debug!("complete grid breaks the rules");
+                return Err(SolveError::Unsatisfiable);
+            }
+            if options.is_classic() {
+                debug!("grid is already solved");
+                let stats = SolveStats {
+                    preprocessed_cells: if options.preprocess { 81 } else { 0 },
+                    ..Default::default()
+                };
+                return Ok((self.clone(), stats));
+            }
+        }
+
+        let start_formula = Instant::now();
+        let mut solver = Solver::new();
+        if let Some(path) = &options.proof {
+            let file = File::create(path).map_err(|e| {
+                SolveError::Proof(format!("failed to create {}: {e}", path.display()))
+            })?;
+            solver.write_proof(BufWriter::new(file), ProofFormat::Drat);
+        }
+        // Preprocessing needs the formula itself, otherwise the clauses go to the solver directly
+        let preprocess = options.preprocess && options.proof.is_none();
+        let formula = if preprocess {
+            let formula = self.formula(options);
+            solver.add_formula(&formula);
+            Some(formula)
+        } else {
+            self.add_formula_to(&mut solver, options);
+            None
+        };
+        let mut stats = SolveStats {
+            formula_time: start_formula.elapsed(),
+            ..Default::default()
+        };
+
+        if let Some(formula) = formula {
+            let Some(assignment) = propagate(&formula) else {
+                debug!("preprocessing found a contradiction");
+                return Err(SolveError::Unsatisfiable);
+            };
+            for (index, value) in assignment.into_iter().enumerate() {
+                if let Some(value) = value {
+                    solver.add_clause(&[Var::from_index(index).lit(value)]);
+                    if value && index < NUM_VARS {
+                        stats.preprocessed_cells += 1;
+                    }
+                }
+            }
+            debug!(
+                "preprocessing determined {} cells",
+                stats.preprocessed_cells
+            );
+        }
+
+        // Solve the damn thing
+        let start_solve = Instant::now();
+        // Writing the proof is the only way the solver can fail because of the options
+        let satisfiable = if options.proof.is_some() {
+            solver
+                .solve()
+                .map_err(|e| SolveError::Proof(e.to_string()))?
+        } else {
+            run_solver(&mut solver)?
+        };
+        stats.solve_time = start_solve.elapsed();
+        if options.proof.is_some() {
+            solver
+                .close_proof()
+                .map_err(|e| SolveError::Proof(e.to_string()))?;
+        }
+        record!("satisfiable", satisfiable);
+        debug!(
+            "solver verdict: satisfiable = {satisfiable} ({:?})",
+            stats.solve_time
+        );
+        if !satisfiable {
+            return Err(SolveError::Unsatisfiable);
+        }
+
+        // Get the values from the model
+        let model = last_model(&solver)?;
+        let mut solution = self.clone().apply_model(&model)?;
+        if options.canonical {
+            solution = self.canonicalize(&mut solver, solution)?;
+        }
+        if options.verify {
+            if !options.satisfies_rules(&solution) {
+                return Err(SolveError::InternalInconsistency(
+                    "solution breaks the sudoku rules".to_string(),
+                ));
+            }
+            if let Some((x, y, _)) = self
+                .filled_cells()
+                .find(|&(x, y, d)| solution.get(x, y) != Some(d))
+            {
+                return Err(SolveError::InternalInconsistency(format!(
+                    "solution changed the filled in cell ({x}, {y})"
+                )));
+            }
+        }
+        Ok((solution, stats))
+    }
+
+    /// Solves the grid `iterations` times with a fresh solver each time, constructing the formula
+    /// only once. The `formula_time` of each iteration is the time spent loading the cached
+    /// formula, so comparing it with [`Grid::solve_with`] shows what constructing the formula
+    /// costs. Only available with the `bench` feature.
+    #[cfg(feature = "bench")]
+    pub fn solve_repeated(&self, iterations: usize) -> Result<Vec<SolveStats>, SolveError> {
+        let formula = self.formula(&SolveOptions::default());
+        (0..iterations)
+            .map(|_| {
+                let start_formula = Instant::now();
+                let mut solver = Solver::new();
+                solver.add_formula(&formula);
+                let formula_time = start_formula.elapsed();
+
+                let start_solve = Instant::now();
+                if !run_solver(&mut solver)? {
+                    return Err(SolveError::Unsatisfiable);
+                }
+                Ok(SolveStats {
+                    solve_time: start_solve.elapsed(),
+                    formula_time,
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    /// Solves the grid and returns the full model found by the solver instead of decoding it.
+    /// Variable `y * 81 + x * 9 + d` is true iff cell `(x, y)` contains digit `d`.
+    pub fn solve_raw(&self) -> Result<Vec<Lit>, SolveError> {
+        let mut solver = self.solver();
+        if !run_solver(&mut solver)? {
+            return Err(SolveError::Unsatisfiable);
+        }
+        last_model(&solver)
+    }
+
+    /// Solves the grid starting from the digits of `hint`, typically the solution of a similar
+    /// puzzle such as one that differs in a single clue.
+    ///
+    /// varisat has no way to set the phases of variables, so the digits of `hint` in the empty
+    /// cells are tried as assumptions first. If the grid has a solution that agrees with them it
+    /// is found without searching. Otherwise the solver continues without them; it picks the
+    /// value a variable had last when deciding on it, so the digits of `hint` that were assigned
+    /// still guide that search. Either way the result is a solution of the grid, though for a
+    /// grid with several solutions not necessarily the one [`Grid::solve`] finds.
+    ///
+    /// Classic puzzles are solved quickly enough that building the formula takes most of the
+    /// time, so on the neighbors in the benchmark this is no faster than [`Grid::solve`].
+    pub fn solve_warm(&self, hint: &Grid) -> Result<Grid, SolveError> {
+        let mut solver = self.solver();
+        let assumptions = hint
+            .filled_cells()
+            .filter(|&(x, y, _)| self.get(x, y).is_none())
+            .map(|(x, y, d)| var_for(x, y, d as usize).positive())
+            .collect::<Vec<_>>();
+        solver.assume(&assumptions);
+        if !run_solver(&mut solver)? {
+            solver.assume(&[]);
+            if !run_solver(&mut solver)? {
+                return Err(SolveError::Unsatisfiable);
+            }
+        }
+        let model = last_model(&solver)?;
+        self.clone().apply_model(&model)
+    }
+
+    /// Solves the grid and returns the 81 digits 1-9 of the solution in row-major order, the
+    /// flattened [`Grid::to_array`], for callers that only deal in bytes.
+    pub fn solve_to_vec(&self) -> Result<Vec<u8>, SolveError> {
+        let solution = self.clone().solve()?;
+        Ok(solution.to_array().as_flattened().to_vec())
+    }
+
+    /// Returns a solver loaded with the sudoku rules but no clues, to be shared by calls to
+    /// [`Grid::solve_in`].
+    pub fn base_solver() -> Solver<'static> {
+        Grid::empty().solver()
+    }
+
+    /// Solves the grid with `base`, a solver that already holds the sudoku rules, such as one
+    /// from [`Grid::base_solver`]. varisat solvers cannot be cloned, so instead of adding the
+    /// clues to a copy they are passed as assumptions: `base` is left without them and can be
+    /// reused for the next grid, keeping the rules and whatever it learned while solving.
+    pub fn solve_in(&self, base: &mut Solver) -> Result<Grid, SolveError> {
+        let assumptions = self
+            .clue_assumptions()
+            .into_iter()
+            .map(|(_, lit)| lit)
+            .collect::<Vec<_>>();
+        base.assume(&assumptions);
+        let model = match run_solver(base) {
+            Ok(true) => last_model(base),
+            Ok(false) => Err(SolveError::Unsatisfiable),
+            Err(e) => Err(e),
+        };
+        // Changing the assumptions discards the model, so only do that once it has been read
+        base.assume(&[]);
+
+        let solution = self.clone().apply_model(&model?)?;
+        if !is_valid_solution(&solution) {
+            return Err(SolveError::InternalInconsistency(
+                "the base solver does not hold the sudoku rules".to_string(),
+            ));
+        }
+        Ok(solution)
+    }
+
+    /// Returns whether placing digit `d` at `(x, y)` still allows the grid to be completed.
+    /// Returns [`SolveError::InvalidConstraint`] if the cell or digit is out of range.
+    pub fn can_place(&self, x: usize, y: usize, d: u8) -> Result<bool, SolveError> {
+        if x >= 9 || y >= 9 {
+            return Err(SolveError::InvalidConstraint(format!(
+                "cell ({x}, {y}) is out of bounds"
+            )));
+        }
+        if d >= 9 {
+            return Err(SolveError::InvalidConstraint(format!(
+                "digit {d} is out of range"
+            )));
+        }
+        let mut solver = self.solver();
+        solver.assume(&[var_for(x, y, d as usize).positive()]);
+        let satisfiable = run_solver(&mut solver)?;
+        debug!(
+            "placing {} at ({x}, {y}): satisfiable = {satisfiable}",
+            d + 1
+        );
+        Ok(satisfiable)
+    }
+
+    /// Returns whether the grid can be completed.
+    fn is_satisfiable(&self) -> Result<bool, SolveError> {
+        run_solver(&mut self.solver())
+    }
+
+    /// Returns a set of filled in cells that together contradict the rules, or `None` if the grid
+    /// can be completed. The set is minimal: clearing any of the returned cells resolves this
+    /// particular contradiction, although the grid may contain others.
+    pub fn unsat_core(&self) -> Result<Option<Vec<(usize, usize)>>, SolveError> {
+        // The clues are assumptions, so dropping one does not require a new solver
+        let mut solver = Grid::empty().solver();
+        let clues = self.clue_assumptions();
+        let mut keep = vec![true; clues.len()];
+        let mut satisfiable = |keep: &[bool]| {
+            let assumptions = clues
+                .iter()
+                .zip(keep)
+                .filter(|&(_, &keep)| keep)
+                .map(|(&(_, lit), _)| lit)
+                .collect::<Vec<_>>();
+            solver.assume(&assumptions);
+            run_solver(&mut solver)
+        };
+        if satisfiable(&keep)? {
+            return Ok(None);
+        }
+
+        // Starting with all clues, drop every clue that is not needed for the contradiction.
+        for i in 0..clues.len() {
+            keep[i] = false;
+            if satisfiable(&keep)? {
+                keep[i] = true;
+            }
+        }
+
+        let cells = clues
+            .iter()
+            .zip(&keep)
+            .filter(|&(_, &keep)| keep)
+            .map(|(&(cell, _), _)| cell)
+            .collect::<Vec<_>>();
+        debug!("found unsat core of {} clues", cells.len());
+        Ok(Some(cells))
+    }
+
+    /// Returns the filled in cells `(x, y)` in row-major order along with the literal that puts
+    /// their digit in them, for passing the clues to a solver as assumptions.
+    fn clue_assumptions(&self) -> Vec<((usize, usize), Lit)> {
+        self.filled_cells()
+            .map(|(x, y, d)| ((x, y), var_for(x, y, d as usize).positive()))
+            .collect()
+    }
+
+    /// Returns a set of filled in cells which, when cleared, make the grid solvable, or `None` if
+    /// the grid can already be completed. The set is minimal: restoring any one of the returned
+    /// cells makes the grid unsolvable again.
+    pub fn suggest_repair(&self) -> Result<Option<Vec<(usize, usize)>>, SolveError> {
+        // Break contradictions one at a time by clearing a cell of their core.
+        let mut repaired = self.clone();
+        let mut cleared = Vec::new();
+        while let Some(core) = repaired.unsat_core()? {
+            let Some(&(x, y)) = core.first() else {
+                return Err(SolveError::InternalInconsistency(
+                    "the rules contradict each other without any clues".to_string(),
+                ));
+            };
+            repaired.cells[y * 9 + x] = None;
+            cleared.push((x, y));
+        }
+        if cleared.is_empty() {
+            return Ok(None);
+        }
+
+        // Clearing a later cell may have resolved an earlier contradiction as well, so restore
+        // every cell that is not actually needed.
+        let mut removed = Vec::new();
+        for (x, y) in cleared {
+            repaired.cells[y * 9 + x] = self.get(x, y);
+            if !repaired.is_satisfiable()? {
+                repaired.cells[y * 9 + x] = None;
+                removed.push((x, y));
+            }
+        }
+
+        debug!("suggesting to remove {} clues", removed.len());
+        Ok(Some(removed))
+    }
+
+    /// Counts the solutions of the grid, stopping once `limit` solutions have been found. A result
+    /// of `limit` therefore means "at least `limit`"; [`Grid::solutions`] tells whether there are
+    /// more.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip(self), fields(clues = self.clue_count(), count))
+    )]
+    pub fn count_solutions(&self, limit: usize) -> Result<usize, SolveError> {
+        let count = count_models(&mut self.solver(), limit)?;
+        record!("count", count);
+        debug!("counted {count} solutions (limit {limit})");
+        Ok(count)
+    }
+
+    /// Returns for every cell in row-major order the Shannon entropy, in bits, of its digit over
+    /// the first `sample_limit` solutions found by [`Grid::for_each_solution`]. Cells with the
+    /// same digit in every sampled solution, such as the filled in cells, have an entropy of 0;
+    /// a cell that is 1 in half of them and 2 in the other half has an entropy of 1. The solver
+    /// does not pick the solutions uniformly, so for grids with more solutions than the limit
+    /// this is only an indication of which cells are ambiguous; [`CellEntropy::truncated`] tells
+    /// whether that is the case, by searching for one more solution like [`Grid::solutions`].
+    /// All zero if there is no solution.
+    pub fn cell_entropy(&self, sample_limit: usize) -> Result<CellEntropy, SolveError> {
+        let mut counts = [[0usize; 9]; 81];
+        let mut total = 0;
+        let found = self.for_each_solution(sample_limit.saturating_add(1), |solution| {
+            if total == sample_limit {
+                return ControlFlow::Continue(());
+            }
+            total += 1;
+            for (i, cell) in solution.cells.iter().enumerate() {
+                if let Some(d) = cell {
+                    counts[i][*d as usize] += 1;
+                }
+            }
+            ControlFlow::Continue(())
+        })?;
+        let entropy = counts.map(|cell| {
+            let entropy = cell
+                .iter()
+                .filter(|&&n| n > 0)
+                .map(|&n| {
+                    let p = n as f32 / total as f32;
+                    -p * p.log2()
+                })
+                .sum::<f32>();
+            // A single digit gives -1 * log2(1), which is -0
+            entropy.max(0.0)
+        });
+        Ok(CellEntropy {
+            entropy,
+            truncated: found > total,
+        })
+    }
+
+    /// Collects up to `limit` solutions of the grid, like [`Grid::for_each_solution`]. To tell
+    /// whether the limit cut the enumeration short, one more solution is searched for, so
+    /// [`Solutions::truncated`] is exact. The limit keeps nearly empty grids, with their
+    /// astronomical number of solutions, from running forever.
+    pub fn solutions(&self, limit: usize) -> Result<Solutions, SolveError> {
+        let mut grids = Vec::new();
+        let count = self.for_each_solution(limit.saturating_add(1), |solution| {
+            if grids.len() < limit {
+                grids.push(solution.clone());
+            }
+            ControlFlow::Continue(())
+        })?;
+        Ok(Solutions {
+            grids,
+            truncated: count > limit,
+        })
+    }
+
+    /// Counts the solutions of the grid under `options` like [`Grid::count_solutions`], for
+    /// example to count solutions up to symmetry with [`SolveOptions::break_digit_symmetry`].
+    pub fn count_solutions_with(
+        &self,
+        options: &SolveOptions,
+        limit: usize,
+    ) -> Result<usize, SolveError> {
+        options.validate()?;
+        count_models(&mut self.solver_with(options), limit)
+    }
+
+    /// Calls `f` with every solution of the grid, one at a time, until `limit` solutions have
+    /// been found or `f` returns [`ControlFlow::Break`]. Returns the number of solutions passed to
+    /// `f`. Unlike collecting them first, this lets the caller stop as soon as it has seen enough.
+    /// Reaching the limit is not an error; whether more solutions exist is not checked. A solver
+    /// failure ends the enumeration with an error instead of passing it off as the last solution.
+    pub fn for_each_solution(
+        &self,
+        limit: usize,
+        mut f: impl FnMut(&Grid) -> ControlFlow<()>,
+    ) -> Result<usize, SolveError> {
+        let mut solver = self.solver();
+        let mut count = 0;
+        while count < limit && run_solver(&mut solver)? {
+            let model = last_model(&solver)?;
+            let solution = self.clone().apply_model(&model)?;
+            count += 1;
+            if f(&solution).is_break() {
+                break;
+            }
+
+            // Block the solution that was just found
+            let blocking_clause = model
+                .into_iter()
+                .filter(|lit| lit.is_positive() && lit.index() < NUM_VARS)
+                .map(|lit| !lit)
+                .collect::<Vec<_>>();
+            solver.add_clause(&blocking_clause);
+        }
+        Ok(count)
+    }
+
+    /// Counts all solutions of the grid with a plain backtracking search over the cells, without
+    /// the SAT solver. Unlike [`Grid::count_solutions`] the count is not capped, but the search
+    /// gives up and returns `None` after visiting a million nodes, which grids with many
+    /// solutions easily exceed.
+    pub fn count_solutions_exact(&self) -> Option<u64> {
+        let mut state = CountState {
+            cells: self.cells,
+            rows: [0; 9],
+            columns: [0; 9],
+            boxes: [0; 9],
+            nodes: 0,
+        };
+        for (x, y, d) in self.filled_cells() {
+            let i = y * 9 + x;
+            if state.allowed(i) & (1 << d) == 0 {
+                // The digit already appears in the row, column or box
+                return Some(0);
+            }
+            state.toggle(i, d);
+        }
+
+        let count = state.count();
+        debug!("counted {count:?} solutions in {} nodes", state.nodes);
+        count
+    }
+
+    /// Returns whether the grid has no, a unique or multiple solutions.
+    pub fn solution_status(&self) -> Result<SolutionStatus, SolveError> {
+        Ok(match self.count_solutions(2)? {
+            0 => SolutionStatus::None,
+            1 => SolutionStatus::Unique,
+            _ => SolutionStatus::Multiple,
+        })
+    }
+
+    /// Returns the (0-based) digit of cell `(x, y)` in the solution, for example to reveal a single
+    /// cell as a hint. A filled in cell is returned as is, without solving. If the grid has more
+    /// than one solution the digit comes from one of them; use [`Grid::determined_cells`] to find
+    /// the cells that are the same in all of them.
+    pub fn solve_cell(&self, x: usize, y: usize) -> Result<u8, SolveError> {
+        if x >= 9 || y >= 9 {
+            return Err(SolveError::InvalidConstraint(format!(
+                "cell ({x}, {y}) is out of bounds"
+            )));
+        }
+        if let Some(d) = self.get(x, y) {
+            return Ok(d);
+        }
+        let solution = self.clone().solve()?;
+        solution.get(x, y).ok_or_else(|| {
+            SolveError::InternalInconsistency(format!("cell ({x}, {y}) is empty in the solution"))
+        })
+    }
+
+    /// Returns the empty cells `(x, y, digit)` that contain the same digit in every solution, in
+    /// row-major order. For a grid with a unique solution these are all empty cells, for a grid
+    /// without solutions there are none. This does not enumerate the solutions: it solves at most
+    /// once per empty cell, so it needs no limit even for an empty grid.
+    pub fn determined_cells(&self) -> Result<Vec<(usize, usize, u8)>, SolveError> {
+        let mut solver = self.solver();
+        if !run_solver(&mut solver)? {
+            return Ok(Vec::new());
+        }
+        let decode = |solver: &Solver| self.clone().apply_model(&last_model(solver)?);
+        let solution = decode(&solver)?;
+
+        // A cell is determined if no solution has a different digit in it. Every other solution
+        // found along the way rules out all cells in which it differs.
+        let mut determined = [true; 81];
+        for i in (0..81).filter(|&i| self.cells[i].is_none()) {
+            if !determined[i] {
+                continue;
+            }
+            let Some(d) = solution.cells[i] else {
+                return Err(SolveError::InternalInconsistency(format!(
+                    "cell ({}, {}) is empty in the solution",
+                    i % 9,
+                    i / 9
+                )));
+            };
+            solver.assume(&[var_for(i % 9, i / 9, d as usize).negative()]);
+            if run_solver(&mut solver)? {
+                let other = decode(&solver)?;
+                for (j, cell) in determined.iter_mut().enumerate() {
+                    *cell &= other.cells[j] == solution.cells[j];
+                }
+            }
+        }
+
+        Ok(solution
+            .filled_cells()
+            .filter(|&(x, y, _)| self.cells[y * 9 + x].is_none() && determined[y * 9 + x])
+            .collect())
+    }
+
+    /// Fills in the [determined cells](Grid::determined_cells) and leaves the cells that differ
+    /// between solutions empty. Unlike [`Grid::solve`] this never picks one of several solutions.
+    /// A grid without solutions is returned unchanged.
+    pub fn safe_fill(&self) -> Result<Grid, SolveError> {
+        let mut grid = self.clone();
+        for (x, y, d) in self.determined_cells()? {
+            grid[(x, y)] = Some(d);
+        }
+        Ok(grid)
+    }
+
+    /// Returns true if the grid has exactly one solution. Also returns false if the solver fails,
+    /// which [`Grid::solution_status`] reports as an error instead.
+    pub fn has_unique_solution(&self) -> bool {
+        self.solution_status() == Ok(SolutionStatus::Unique)
+    }
+
+    /// Returns true if the grid has a unique solution which is lost when clearing any of the
+    /// filled in cells.
+    pub fn is_minimal(&self) -> Result<bool, SolveError> {
+        let solution = match self.clone().solve() {
+            Ok(solution) => solution,
+            Err(SolveError::Unsatisfiable) => return Ok(false),
+            Err(e) => return Err(e),
+        };
+
+        // Every solution of the solver differs from `solution`, and the clues are assumptions so
+        // one can be left out without building a new solver.
+        let mut solver = Grid::empty().solver();
+        solver.add_clause(
+            &solution
+                .filled_cells()
+                .map(|(x, y, d)| var_for(x, y, d as usize).negative())
+                .collect::<Vec<_>>(),
+        );
+        let clues = self
+            .clue_assumptions()
+            .into_iter()
+            .map(|(_, lit)| lit)
+            .collect::<Vec<_>>();
+        solver.assume(&clues);
+        if run_solver(&mut solver)? {
+            return Ok(false);
+        }
+
+        for i in 0..clues.len() {
+            let mut assumptions = clues.clone();
+            assumptions.remove(i);
+            solver.assume(&assumptions);
+            if !run_solver(&mut solver)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Returns cells `(x, y, digit)` of `solution`, which must be a solution of this grid, that
+    /// give the grid a unique solution when added as clues. The cells are picked greedily: as long
+    /// as another solution exists, one of the cells in which it differs from `solution` is added,
+    /// choosing the one that leaves the fewest solutions.
+    ///
+    /// Returns [`SolveError::InvalidConstraint`] if `solution` is not a solution of this grid.
+    pub fn clues_to_unique(&self, solution: &Grid) -> Result<Vec<(usize, usize, u8)>, SolveError> {
+        const CAP: usize = 16;
+
+        if !is_valid_solution(solution)
+            || self
+                .filled_cells()
+                .any(|(x, y, d)| solution.get(x, y) != Some(d))
+        {
+            return Err(SolveError::InvalidConstraint(
+                "the given solution is not a solution of the grid".to_string(),
+            ));
+        }
+
+        let mut grid = self.clone();
+        let mut clues = Vec::new();
+        loop {
+            let other = match grid.solve_excluding(solution) {
+                Ok(other) => other,
+                Err(SolveError::Unsatisfiable) => break,
+                Err(e) => return Err(e),
+            };
+            let mut best = None;
+            for (x, y, d) in solution
+                .filled_cells()
+                .filter(|&(x, y, d)| other.get(x, y) != Some(d))
+            {
+                let mut candidate = grid.clone();
+                candidate.cells[y * 9 + x] = Some(d);
+                let count = candidate.count_solutions(CAP)?;
+                if best.is_none_or(|(best_count, _)| count < best_count) {
+                    best = Some((count, (x, y, d)));
+                }
+            }
+            let Some((_, (x, y, d))) = best else {
+                return Err(SolveError::InternalInconsistency(
+                    "the other solution does not differ from the given one".to_string(),
+                ));
+            };
+            grid.cells[y * 9 + x] = Some(d);
+            clues.push((x, y, d));
+        }
+
+        debug!("{} clues needed for a unique solution", clues.len());
+        Ok(clues)
+    }
+
+    /// Reveals random cells (determined by `seed`) of `solution` on top of the filled in cells of
+    /// this grid until about `fraction` of the 81 cells are filled in. This is plain masking for
+    /// demonstrations and tutorials: unlike [`Grid::reduce_to_minimal`] the result need not have a
+    /// unique solution.
+    pub fn reveal_fraction(&self, solution: &Grid, fraction: f32, seed: u64) -> Grid {
+        let target = (fraction.clamp(0.0, 1.0) * 81.0).round() as usize;
+        let mut order = (0..81)
+            .filter(|&i| self.cells[i].is_none() && solution.cells[i].is_some())
+            .collect::<Vec<_>>();
+        Rng::new(seed).shuffle(&mut order);
+
+        let mut grid = self.clone();
+        let missing = target.saturating_sub(grid.clue_count());
+        for i in order.into_iter().take(missing) {
+            grid.cells[i] = solution.cells[i];
+        }
+        grid
+    }
+
+    /// Returns the inverse of this puzzle: the cells that are empty here are filled in with the
+    /// value from `solution`, and the clues are cleared. Together the grid and its complement
+    /// make up the solution, so the complement shows what the solver had to find.
+    pub fn complement(&self, solution: &Grid) -> Grid {
+        let mut grid = Grid::empty();
+        for i in (0..81).filter(|&i| self.cells[i].is_none()) {
+            grid.cells[i] = solution.cells[i];
+        }
+        grid
+    }
+
+    /// Clears filled in cells in a random order (determined by `seed`) as long as the grid keeps
+    /// a unique solution. The result is a minimal puzzle with the same solution. A grid without a
+    /// unique solution is returned unchanged.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip(self), fields(clues = self.clue_count(), remaining))
+    )]
+    pub fn reduce_to_minimal(&self, seed: u64) -> Grid {
+        let mut order = (0..81).collect::<Vec<_>>();
+        Rng::new(seed).shuffle(&mut order);
+
+        let mut reduced = self.clone();
+        if !reduced.has_unique_solution() {
+            return reduced;
+        }
+
+        // Clearing more cells never removes solutions, so a single pass suffices: a cell that is
+        // needed now is still needed after clearing others.
+        for i in order {
+            if let Some(d) = reduced.cells[i].take() {
+                if !reduced.has_unique_solution() {
+                    reduced.cells[i] = Some(d);
+                }
+            }
+        }
+
+        debug!("reduced grid to {} clues", reduced.clue_count());
+        record!("remaining", reduced.clue_count());
+        reduced
+    }
+
+    /// Reduces a solved grid to a minimal puzzle with that solution, trying a few random orders
+    /// (determined by `seed`) with [`Grid::reduce_to_minimal`] and keeping the one with the fewest
+    /// clues, whose number [`Grid::clue_count`] reports. Each attempt is greedy, so the result is
+    /// minimal but need not have the smallest number of clues possible for this solution, which
+    /// would take an exhaustive search. A grid that is not a valid solution is returned unchanged.
+    pub fn minimal_clue_puzzle(&self, seed: u64) -> Grid {
+        const ATTEMPTS: usize = 3;
+
+        if !is_valid_solution(self) {
+            return self.clone();
+        }
+        let mut rng = Rng::new(seed);
+        let puzzle = (0..ATTEMPTS)
+            .map(|_| self.reduce_to_minimal(rng.next_u64()))
+            .min_by_key(Grid::clue_count)
+            .unwrap_or_else(|| self.clone());
+        debug!("fewest clues found: {}", puzzle.clue_count());
+        puzzle
+    }
+
+    /// Tries to find a puzzle with a unique solution whose clues are exactly the cells `pattern`,
+    /// by keeping those cells of random solutions (determined by `seed`). Returns `None` if none
+    /// of the tried solutions works, which is always the case for fewer than 17 clues. Finding
+    /// nothing does not prove that the pattern cannot be filled in.
+    pub fn fill_pattern(pattern: &[(usize, usize)], seed: u64) -> Option<Grid> {
+        const ATTEMPTS: usize = 100;
+
+        // No sudoku with fewer than 17 clues has a unique solution
+        let mut cells = pattern.to_vec();
+        cells.sort_unstable();
+        cells.dedup();
+        if cells.len() < 17 {
+            return None;
+        }
+
+        let mut rng = Rng::new(seed);
+        let puzzle = (0..ATTEMPTS)
+            .map(|_| Grid::generate_filled(rng.next_u64()).keep_only(&cells))
+            .find(|puzzle| puzzle.has_unique_solution());
+        debug!(
+            "{} a puzzle for a pattern of {} clues",
+            if puzzle.is_some() {
+                "found"
+            } else {
+                "did not find"
+            },
+            cells.len()
+        );
+        puzzle
+    }
+
+    /// Solves the grid, looking for a solution other than `forbidden`. Only the filled in cells of
+    /// `forbidden` are taken into account: the solution differs from it in at least one of them.
+    pub fn solve_excluding(&self, forbidden: &Grid) -> Result<Grid, SolveError> {
+        let mut solver = self.solver();
+
+        // Block the forbidden assignment
+        let blocking_clause = forbidden
+            .filled_cells()
+            .map(|(x, y, d)| var_for(x, y, d as usize).negative())
+            .collect::<Vec<_>>();
+        solver.add_clause(&blocking_clause);
+
+        if !run_solver(&mut solver)? {
+            return Err(SolveError::Unsatisfiable);
+        }
+        self.clone().apply_model(&last_model(&solver)?)
+    }
+
+    /// Solves the grid, preferring a solution that agrees with the filled in cells of `preferred`.
+    /// The cells of the template are tried in row-major order and kept whenever they are
+    /// consistent with the ones kept before, so every cell of the template that can be honored on
+    /// its own, given the earlier ones, ends up in the solution.
+    pub fn solve_biased(&self, preferred: &Grid) -> Result<Grid, SolveError> {
+        let mut solver = self.solver();
+        if !run_solver(&mut solver)? {
+            return Err(SolveError::Unsatisfiable);
+        }
+        let mut solution = self.clone().apply_model(&last_model(&solver)?)?;
+
+        let mut assumptions = Vec::new();
+        for (x, y, d) in preferred.filled_cells() {
+            let lit = var_for(x, y, d as usize).positive();
+            if solution.get(x, y) == Some(d) {
+                // The current solution already agrees, no need to check
+                assumptions.push(lit);
+                continue;
+            }
+
+            assumptions.push(lit);
+            solver.assume(&assumptions);
+            if run_solver(&mut solver)? {
+                solution = self.clone().apply_model(&last_model(&solver)?)?;
+            } else {
+                trace!("dropping preferred {} at ({x}, {y})", d + 1);
+                assumptions.pop();
+            }
+        }
+
+        Ok(solution)
+    }
+
+    /// Solves the grid while keeping as many of the filled in cells of `attempt` as possible, for
+    /// finishing a grid that a player filled in partially and maybe wrongly. The smallest number
+    /// of entries of the attempt that cannot be part of a solution are dropped.
+    pub fn complete_preserving(&self, attempt: &Grid) -> Result<Grid, SolveError> {
+        let mut solver = self.solver();
+
+        // Every entry is either kept or dropped
+        let entries = attempt.filled_cells().collect::<Vec<_>>();
+        let dropped = entries
+            .iter()
+            .map(|&(x, y, d)| {
+                let dropped = solver.new_var();
+                let cell = var_for(x, y, d as usize);
+                solver.add_clause(&[dropped.positive(), cell.positive()]);
+                dropped
+            })
+            .collect::<Vec<_>>();
+
+        // Sequential counter: `at_least[j]` is true if more than `j` entries are dropped
+        let n = entries.len();
+        let mut at_least: Vec<Var> = Vec::new();
+        for &drop in &dropped {
+            let next = (0..n).map(|_| solver.new_var()).collect::<Vec<_>>();
+            solver.add_clause(&[drop.negative(), next[0].positive()]);
+            for (j, &prev) in at_least.iter().enumerate() {
+                solver.add_clause(&[prev.negative(), next[j].positive()]);
+                if j + 1 < n {
+                    solver.add_clause(&[drop.negative(), prev.negative(), next[j + 1].positive()]);
+                }
+            }
+            at_least = next;
+        }
+
+        // Allow one more dropped entry at a time until there is a solution
+        for k in 0..=n {
+            match at_least.get(k) {
+                Some(more) => solver.assume(&[more.negative()]),
+                None => solver.assume(&[]),
+            }
+            if run_solver(&mut solver)? {
+                debug!("dropped {k} of {n} entries");
+                return self.clone().apply_model(&last_model(&solver)?);
+            }
+        }
+        Err(SolveError::Unsatisfiable)
+    }
+
+    /// Returns the lexicographically smallest solution of the grid, reading the cells in row-major
+    /// order. Unlike [`Grid::solve`] the result does not depend on the model the solver happens to
+    /// find, so puzzles with multiple solutions always produce the same output.
+    pub fn solve_canonical(&self) -> Result<Grid, SolveError> {
+        debug!("solving grid canonically with {} clues", self.clue_count());
+        let mut solver = self.solver();
+        if !run_solver(&mut solver)? {
+            return Err(SolveError::Unsatisfiable);
+        }
+        let solution = self.clone().apply_model(&last_model(&solver)?)?;
+        self.canonicalize(&mut solver, solution)
+    }
+
+    /// Turns `solution`, the last model found by `solver`, into the lexicographically smallest
+    /// solution the solver allows.
+    fn canonicalize(&self, solver: &mut Solver, mut solution: Grid) -> Result<Grid, SolveError> {
+        // Fix the cells one by one to the smallest digit that still allows a solution. The last
+        // model found always is a valid completion of the cells fixed so far, so only digits
+        // smaller than the one in that model have to be tried.
+        let mut assumptions = Vec::with_capacity(81);
+        for y in 0..9 {
+            for x in 0..9 {
+                let Some(current) = solution.get(x, y) else {
+                    return Err(SolveError::InternalInconsistency(format!(
+                        "the solution leaves cell ({x}, {y}) empty"
+                    )));
+                };
+                for d in 0..current {
+                    assumptions.push(var_for(x, y, d as usize).positive());
+                    solver.assume(&assumptions);
+                    if run_solver(solver)? {
+                        trace!("lowered ({x}, {y}) from {} to {}", current + 1, d + 1);
+                        solution = self.clone().apply_model(&last_model(solver)?)?;
+                        break;
+                    }
+                    assumptions.pop();
+                }
+                if assumptions.len() == y * 9 + x {
+                    assumptions.push(var_for(x, y, current as usize).positive());
+                }
+            }
+        }
+        Ok(solution)
+    }
+}
+
+/// Returns true if the grid is completely filled in and every row, column and box contains each
+/// digit exactly once.
+pub fn is_valid_solution(grid: &Grid) -> bool {
+    SolveOptions::default().satisfies_rules(grid)
+}
+
+/// Returns whether the nine cells contain each digit exactly once.
+fn has_every_digit(unit: [Cell; 9]) -> bool {
+    let mask = unit
+        .into_iter()
+        .try_fold(0u16, |mask, cell| cell.map(|d| mask | 1 << d));
+    mask == Some(0x1ff)
+}
+
+/// Describes a character that is not part of the grid format. Characters outside of ASCII are
+/// shown with their code point, since many of them look just like a digit or a space.
+fn invalid_character(c: char) -> String {
+    match c {
+        '\u{ff11}'..='\u{ff19}' => format!(
+            "invalid character '{c}' (U+{:04X}), a full-width digit; use Grid::from_str_lenient to accept it",
+            c as u32
+        ),
+        _ if c.is_ascii() => format!("invalid character '{c}'"),
+        _ => format!("invalid character '{c}' (U+{:04X})", c as u32),
+    }
+}
+
+/// Describes why a line of `len` cells is not a grid. Grids of other orders, such as 4x4 with 16
+/// cells or 16x16 with 256, are recognized by their length so the error can say that only 9x9
+/// grids are supported.
+fn wrong_cell_count(len: usize) -> String {
+    let order = (1..=len)
+        .take_while(|n| n * n <= len)
+        .find(|n| n * n == len);
+    let box_size = order.and_then(|n| (2..=n).take_while(|b| b * b <= n).find(|b| b * b == n));
+    match (order, box_size) {
+        (Some(n), Some(_)) => {
+            format!("{len} cells make a {n}x{n} grid, but only 9x9 grids are supported")
+        }
+        _ => format!(
+            "expected 81 cells, got {len}, which is not the size of a grid with square boxes"
+        ),
+    }
+}
+
+/// Parses a grid in the line format accepted by [`Grid::from_str`], solves it and returns the
+/// solution in the same format.
+pub fn solve_str(input: &str) -> Result<String, SolveError> {
+    let grid = Grid::from_str(input).map_err(SolveError::Parse)?;
+    Ok(grid.solve()?.to_line())
+}
+
+impl Index<(usize, usize)> for Grid {
+    type Output = Cell;
+
+    fn index(&self, (x, y): (usize, usize)) -> &Cell {
+        assert!(x < 9 && y < 9, "cell ({x}, {y}) is out of bounds");
+        &self.cells[y * 9 + x]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Grid {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Cell {
+        assert!(x < 9 && y < 9, "cell ({x}, {y}) is out of bounds");
+        &mut self.cells[y * 9 + x]
+    }
+}
+
+/// The nine characters used to write the digits 1-9, for example colors or letters in place of
+/// the usual numbers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alphabet([char; 9]);
+
+impl Alphabet {
+    /// The regular digits `1`-`9`.
+    pub const DIGITS: Alphabet = Alphabet(['1', '2', '3', '4', '5', '6', '7', '8', '9']);
+
+    /// Constructs an alphabet from a string of nine distinct characters. [`Grid::EMPTY`] is
+    /// reserved for empty cells.
+    pub fn new(symbols: &str) -> Result<Alphabet, String> {
+        let symbols: [char; 9] = symbols
+            .chars()
+            .collect::<Vec<_>>()
+            .try_into()
+            .map_err(|e: Vec<char>| format!("alphabet has {} characters instead of 9", e.len()))?;
+        for (i, &c) in symbols.iter().enumerate() {
+            if c == Grid::EMPTY {
+                return Err(format!(
+                    "alphabet cannot contain {:?}, which marks empty cells",
+                    Grid::EMPTY
+                ));
+            }
+            if symbols[..i].contains(&c) {
+                return Err(format!("alphabet contains '{c}' twice"));
+            }
+        }
+        Ok(Alphabet(symbols))
+    }
+
+    /// Returns the character for the (0-based) digit `d`.
+    pub fn symbol(&self, d: u8) -> char {
+        self.0[d as usize]
+    }
+
+    /// Returns the (0-based) digit written as `c`, if any.
+    pub fn digit(&self, c: char) -> Option<u8> {
+        self.0.iter().position(|&s| s == c).map(|d| d as u8)
+    }
+}
+
+/// Displays a grid using a custom alphabet, see [`Grid::display_with_alphabet`].
+pub struct AlphabetDisplay<'a> {
+    grid: &'a Grid,
+    alphabet: &'a Alphabet,
+    style: DisplayStyle,
+    /// Cells `(x, y)` whose glyph is wrapped in [`HIGHLIGHT_START`] and [`HIGHLIGHT_END`].
+    highlight: &'a [(usize, usize)],
+}
+
+/// The ANSI escape code that starts a highlighted cell in [`Grid::format_highlighted`], bold red.
+pub const HIGHLIGHT_START: &str = "\x1b[1;31m";
+/// The ANSI escape code that ends a highlighted cell.
+pub const HIGHLIGHT_END: &str = "\x1b[0m";
+
+/// Layout of the text produced by [`Grid::format_with`]. The default is the layout of the
+/// [`Display`] implementation of [`Grid`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisplayStyle {
+    /// Print the columns of the grid as lines instead of the rows.
+    pub column_major: bool,
+    /// Follow every cell by a space.
+    pub separators: bool,
+    /// Draw lines between the boxes.
+    pub block_lines: bool,
+    /// The character printed for empty cells.
+    pub empty: char,
+}
+
+impl Default for DisplayStyle {
+    fn default() -> Self {
+        Self {
+            column_major: false,
+            separators: true,
+            block_lines: true,
+            empty: Grid::EMPTY,
+        }
+    }
+}
+
+impl Grid {
+    /// Parses a grid in the same format as [`Grid::from_str`], reading the digits from the
+    /// characters of `alphabet` instead of `1`-`9`.
+    pub fn from_str_with_alphabet(s: &str, alphabet: &Alphabet) -> Result<Grid, String> {
+        // A single line read from a file or stdin still ends in a line break
+        let s = s
+            .strip_suffix('\n')
+            .map_or(s, |s| s.strip_suffix('\r').unwrap_or(s));
+        if s.contains('\n') {
+            return Grid::from_lines_with_alphabet(s, alphabet);
+        }
+        Ok(Self {
+            cells: s
+                .chars()
+                .map(|c| match c {
+                    Grid::EMPTY => Ok(None),
+                    _ => alphabet
+                        .digit(c)
+                        .map(Some)
+                        .ok_or_else(|| invalid_character(c)),
+                })
+                .collect::<Result<Vec<Cell>, _>>()?
+                .try_into()
+                .map_err(|e: Vec<Cell>| wrong_cell_count(e.len()))?,
+        })
+    }
+
+    /// Parses a grid like [`Grid::from_str`], after replacing the full-width digits `１`-`９` by
+    /// `1`-`9` and other kinds of spaces, such as no-break and ideographic spaces, by a regular
+    /// space. Pasted puzzles often contain those; [`Grid::from_str`] rejects them instead.
+    pub fn from_str_lenient(s: &str) -> Result<Grid, String> {
+        let normalized = s
+            .chars()
+            .map(|c| match c {
+                '\u{ff11}'..='\u{ff19}' => char::from(b'1' + (c as u32 - 0xff11) as u8),
+                '\n' | '\r' => c,
+                _ if c.is_whitespace() => Grid::EMPTY,
+                _ => c,
+            })
+            .collect::<String>();
+        Grid::from_str(&normalized)
+    }
+
+    /// Reads a grid from `reader`, stopping after its 81 cells. A cell is a digit 1-9, or `.` or
+    /// `0` for an empty cell. Whitespace and the `|`, `-` and `+` of box lines are ignored, so
+    /// this reads the output of [`Grid::format_with`] with `.` for empty cells. A first line of
+    /// exactly 81 characters is read cell by cell instead, with [`Grid::EMPTY`] as an empty cell
+    /// as well, so the output of [`Grid::to_line`] reads back too.
+    pub fn from_reader(mut reader: impl BufRead) -> Result<Grid, String> {
+        let mut grid = Grid::empty();
+        let mut count = 0;
+        let mut line = String::new();
+        while count < 81 {
+            line.clear();
+            if reader
+                .read_line(&mut line)
+                .map_err(|e| format!("failed to read grid: {e}"))?
+                == 0
+            {
+                return Err(format!("expected 81 cells, got {count}"));
+            }
+            let content = line.trim_end_matches(['\r', '\n']);
+            if count == 0 && content.chars().count() == 81 {
+                for (cell, c) in grid.cells.iter_mut().zip(content.chars()) {
+                    *cell = match c {
+                        '1'..='9' => Some(c as u8 - b'1'),
+                        '.' | '0' | Grid::EMPTY => None,
+                        _ => return Err(invalid_character(c)),
+                    };
+                }
+                break;
+            }
+            for c in line.chars() {
+                let cell = match c {
+                    '1'..='9' => Some(c as u8 - b'1'),
+                    '.' | '0' => None,
+                    '|' | '-' | '+' => continue,
+                    _ if c.is_whitespace() => continue,
+                    _ => return Err(invalid_character(c)),
+                };
+                if count == 81 {
+                    return Err("more than 81 cells".to_string());
+                }
+                grid.cells[count] = cell;
+                count += 1;
+            }
+        }
+        Ok(grid)
+    }
+
+    /// Parses a grid written as nine lines of nine cells. Trailing whitespace is trimmed from every
+    /// line, since pasting from a terminal often adds some, so a line may be shorter than nine
+    /// cells: the missing cells at the end are empty.
+    fn from_lines_with_alphabet(s: &str, alphabet: &Alphabet) -> Result<Grid, String> {
+        let lines = s.lines().collect::<Vec<_>>();
+        if lines.len() != 9 {
+            return Err(format!("expected 9 lines, got {}", lines.len()));
+        }
+
+        let mut cells = String::with_capacity(81);
+        for (y, line) in lines.into_iter().enumerate() {
+            let line = line.trim_end();
+            let len = line.chars().count();
+            if len > 9 {
+                return Err(format!("line {} has {len} cells instead of 9", y + 1));
+            }
+            cells.push_str(line);
+            cells.extend(std::iter::repeat_n(Grid::EMPTY, 9 - len));
+        }
+        Grid::from_str_with_alphabet(&cells, alphabet)
+    }
+
+    /// Returns an object that displays the grid like its [`Display`] implementation, but writes
+    /// the digits using the characters of `alphabet`.
+    pub fn display_with_alphabet<'a>(&'a self, alphabet: &'a Alphabet) -> AlphabetDisplay<'a> {
+        AlphabetDisplay {
+            grid: self,
+            alphabet,
+            style: DisplayStyle::default(),
+            highlight: &[],
+        }
+    }
+
+    /// Formats the grid as text laid out according to `style`.
+    pub fn format_with(&self, style: &DisplayStyle) -> String {
+        self.format_highlighted(style, &[])
+    }
+
+    /// Formats the grid like [`Grid::format_with`], marking the cells `(x, y)` of `highlight` in
+    /// a terminal by wrapping them in the ANSI escape codes [`HIGHLIGHT_START`] and
+    /// [`HIGHLIGHT_END`]. The escape codes take no space on screen, so the layout is unchanged.
+    /// Passing the cells of [`Grid::conflicts`] shows which clues contradict each other.
+    pub fn format_highlighted(&self, style: &DisplayStyle, highlight: &[(usize, usize)]) -> String {
+        AlphabetDisplay {
+            grid: self,
+            alphabet: &Alphabet::DIGITS,
+            style: *style,
+            highlight,
+        }
+        .to_string()
+    }
+}
+
+impl FromStr for Grid {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Grid::from_str_with_alphabet(s, &Alphabet::DIGITS)
+    }
+}
+
+impl Display for AlphabetDisplay<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let style = &self.style;
+        for row in 0..9 {
+            for col in 0..9 {
+                let (x, y) = if style.column_major {
+                    (row, col)
+                } else {
+                    (col, row)
+                };
+                let glyph = match self.grid.get(x, y) {
+                    None => style.empty,
+                    Some(d) => self.alphabet.symbol(d),
+                };
+                if self.highlight.contains(&(x, y)) {
+                    write!(f, "{HIGHLIGHT_START}{glyph}{HIGHLIGHT_END}")?;
+                } else {
+                    write!(f, "{glyph}")?;
+                }
+                if style.separators {
+                    write!(f, " ")?;
+                }
+                if style.block_lines && col < 8 && (col + 1) % 3 == 0 {
+                    write!(f, "{}", if style.separators { "| " } else { "|" })?;
+                }
+            }
+            writeln!(f)?;
+            if style.block_lines && row < 8 && (row + 1) % 3 == 0 {
+                if style.separators {
+                    writeln!(f, "---------------------")?;
+                } else {
+                    writeln!(f, "---+---+---")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Grid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.display_with_alphabet(&Alphabet::DIGITS).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_solve() {
+        let grid: Grid =
+            // "86   7           4 9 58 2    7   8 663   2  5 1  9  3  7   53  3    6     9    1 "
+        // "1    7 9  3  2   8  96  5    53  9   1  8   26    4   3      1  41     7  7   3  "
+        // "1                                                                               2"
+        // "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  "
+        "     6    59     82    8    45        3        6  3 54   325  6                  "
+                .parse()
+                .unwrap();
+
+        let grid = grid.solve().unwrap();
+
+        println!("{grid}");
+    }
+
+    #[test]
+    fn test_solve_canonical() {
+        let grid: Grid = " ".repeat(81).parse().unwrap();
+
+        let first = grid.solve_canonical().unwrap();
+        for _ in 0..3 {
+            assert_eq!(grid.solve_canonical().unwrap(), first);
+        }
+        assert_eq!(
+            (0..9)
+                .map(|x| first.get(x, 0).unwrap() + 1)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5, 6, 7, 8, 9]
+        );
+    }
+
+    #[test]
+    fn test_can_place() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        // The unique solution has a 4 at (2, 0), so anything else leads to a contradiction even
+        // though a 1 does not conflict with any of the filled in values.
+        assert!(grid.can_place(2, 0, 3).unwrap());
+        assert!(!grid.can_place(2, 0, 0).unwrap());
+
+        // Out of range values would otherwise alias another cell or fall outside the formula
+        for (x, y, d) in [(0, 0, 9), (9, 0, 0), (0, 9, 0)] {
+            assert!(matches!(
+                grid.can_place(x, y, d),
+                Err(SolveError::InvalidConstraint(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_suggest_repair() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        assert_eq!(grid.unsat_core().unwrap(), None);
+        assert_eq!(grid.suggest_repair().unwrap(), None);
+
+        // A 1 at (2, 0) does not conflict with any clue directly but has no solution.
+        let mut contradictory = grid.clone();
+        contradictory.cells[2] = Some(0);
+        assert!(contradictory
+            .unsat_core()
+            .unwrap()
+            .unwrap()
+            .contains(&(2, 0)));
+
+        let removed = contradictory.suggest_repair().unwrap().unwrap();
+        assert!(!removed.is_empty());
+        let mut repaired = contradictory.clone();
+        for &(x, y) in &removed {
+            repaired.cells[y * 9 + x] = None;
+        }
+        assert!(repaired.solve().is_ok());
+    }
+
+    /// [`Grid::is_minimal`] with a new solver for every clue that is left out.
+    fn is_minimal_rebuilt(grid: &Grid) -> bool {
+        grid.has_unique_solution()
+            && grid.filled_cells().all(|(x, y, _)| {
+                let mut reduced = grid.clone();
+                reduced[(x, y)] = None;
+                !reduced.has_unique_solution()
+            })
+    }
+
+    /// [`Grid::unsat_core`] with a new solver for every clue that is left out.
+    fn unsat_core_rebuilt(grid: &Grid) -> Option<Vec<(usize, usize)>> {
+        if grid.is_satisfiable().unwrap() {
+            return None;
+        }
+        let mut core = grid.clone();
+        let mut cells = Vec::new();
+        for (x, y, d) in grid.filled_cells() {
+            core[(x, y)] = None;
+            if core.is_satisfiable().unwrap() {
+                core[(x, y)] = Some(d);
+                cells.push((x, y));
+            }
+        }
+        Some(cells)
+    }
+
+    #[test]
+    fn test_assumptions_match_rebuilding() {
+        let puzzle: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let solution = puzzle.clone().solve().unwrap();
+        let minimal = solution.reduce_to_minimal(7);
+        let mut contradictory = puzzle.clone();
+        contradictory[(2, 0)] = Some(0);
+        // A copy of the first clue elsewhere in its row
+        let mut conflicting = minimal.clone();
+        let (x, y, d) = minimal.filled_cells().next().unwrap();
+        conflicting[((x + 1) % 9, y)] = Some(d);
+
+        for grid in [
+            puzzle,
+            solution,
+            minimal,
+            contradictory,
+            conflicting,
+            Grid::empty(),
+        ] {
+            assert_eq!(grid.is_minimal().unwrap(), is_minimal_rebuilt(&grid));
+            assert_eq!(grid.unsat_core().unwrap(), unsat_core_rebuilt(&grid));
+        }
+    }
+
+    #[test]
+    fn test_preprocess() {
+        // This puzzle can be solved by only filling in cells with a single candidate.
+        let grid: Grid =
+            "  3 2 6  9  3 5  1  18 64    81 29  7       8  67 82    26 95  8  2 3  9  5 1 3  "
+                .parse()
+                .unwrap();
+
+        let options = SolveOptions {
+            preprocess: true,
+            ..Default::default()
+        };
+        let (solution, stats) = grid.solve_with(&options).unwrap();
+        assert_eq!(stats.preprocessed_cells, 81);
+        assert_eq!(solution, grid.clone().solve().unwrap());
+    }
+
+    #[test]
+    fn test_candidates_option() {
+        // Hard enough that unit propagation alone does not solve it
+        let grid: Grid =
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  "
+                .parse()
+                .unwrap();
+        let solution = grid.clone().solve().unwrap();
+        let plain = SolveOptions {
+            preprocess: true,
+            ..Default::default()
+        };
+        let (_, plain_stats) = grid.solve_with(&plain).unwrap();
+
+        // Pretend external propagation narrowed the top band down to the solution
+        let mut candidates = grid.candidates();
+        for (x, y, d) in solution.filled_cells().filter(|&(_, y, _)| y < 3) {
+            candidates[y * 9 + x] = [false; 9];
+            candidates[y * 9 + x][d as usize] = true;
+        }
+        let tightened = SolveOptions {
+            candidates: Some(candidates),
+            ..plain
+        };
+        let (tightened_solution, tightened_stats) = grid.solve_with(&tightened).unwrap();
+        assert_eq!(tightened_solution, solution);
+
+        // The solver is left with less to decide
+        assert!(tightened_stats.preprocessed_cells > plain_stats.preprocessed_cells);
+    }
+
+    #[test]
+    fn test_verify() {
+        let options = SolveOptions {
+            verify: true,
+            ..Default::default()
+        };
+        for line in [
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79",
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  ",
+        ] {
+            let grid: Grid = line.parse().unwrap();
+            let (solution, _) = grid.solve_with(&options).unwrap();
+            assert!(is_valid_solution(&solution));
+        }
+    }
+
+    #[test]
+    fn test_solve_complete() {
+        let solution: Grid =
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+                .parse()
+                .unwrap();
+        assert!(solution.is_complete());
+        let (solved, stats) = solution.solve_with(&SolveOptions::default()).unwrap();
+        assert_eq!(solved, solution);
+        assert_eq!(stats.solve_time, Duration::ZERO);
+
+        let mut invalid = solution.clone();
+        invalid.cells.swap(0, 1);
+        assert!(invalid.is_complete());
+        assert_eq!(invalid.solve(), Err(SolveError::Unsatisfiable));
+
+        // The extra constraints still have to be checked
+        let options = SolveOptions {
+            extra_regions: vec![ExtraRegion::main_diagonal()],
+            ..Default::default()
+        };
+        assert_eq!(
+            solution.solve_with(&options).err(),
+            Some(SolveError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn test_proof() {
+        let path = std::env::temp_dir().join(format!("sudosat-proof-{}.drat", std::process::id()));
+        let options = SolveOptions {
+            proof: Some(path.clone()),
+            ..Default::default()
+        };
+
+        // Two 1s in the first row
+        let grid: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert_eq!(
+            grid.solve_with(&options).err(),
+            Some(SolveError::Unsatisfiable)
+        );
+
+        // A DRAT refutation ends by deriving the empty clause
+        let proof = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(proof.lines().any(|line| line.trim() == "0"));
+
+        let options = SolveOptions {
+            proof: Some(std::env::temp_dir().join("missing-dir").join("proof.drat")),
+            ..Default::default()
+        };
+        assert!(matches!(
+            grid.solve_with(&options),
+            Err(SolveError::Proof(_))
+        ));
+    }
+
+    #[test]
+    fn test_canonical_option() {
+        let options = SolveOptions {
+            canonical: true,
+            ..Default::default()
+        };
+        let grid: Grid = format!("{}5", " ".repeat(80)).parse().unwrap();
+        let (first, _) = grid.solve_with(&options).unwrap();
+        for _ in 0..3 {
+            assert_eq!(grid.solve_with(&options).unwrap().0, first);
+        }
+        assert_eq!(first, grid.solve_canonical().unwrap());
+        assert_eq!(first.to_line()[..9], *"123456789");
+
+        // Only solutions that satisfy the constraints are considered
+        let options = SolveOptions {
+            extra_regions: vec![ExtraRegion::main_diagonal()],
+            ..options
+        };
+        let (diagonal, _) = grid.solve_with(&options).unwrap();
+        assert_ne!(diagonal, first);
+        assert!((0..9).all(|i| (0..i).all(|j| diagonal.get(i, i) != diagonal.get(j, j))));
+    }
+
+    #[test]
+    fn test_solve_raw() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        let model = grid.solve_raw().unwrap();
+        assert_eq!(model.len(), 729);
+        assert_eq!(model.iter().filter(|lit| lit.is_positive()).count(), 81);
+    }
+
+    #[test]
+    fn test_solve_warm() {
+        let puzzle: Grid =
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  "
+                .parse()
+                .unwrap();
+        let solution = puzzle.clone().solve().unwrap();
+
+        // A neighbor with one clue less, which may have more solutions
+        let mut neighbor = puzzle.clone();
+        neighbor[(0, 0)] = None;
+        let warm = neighbor.solve_warm(&solution).unwrap();
+        assert!(is_valid_solution(&warm));
+        assert!(neighbor
+            .filled_cells()
+            .all(|(x, y, d)| warm.get(x, y) == Some(d)));
+        assert_eq!(warm, solution);
+
+        // A hint that contradicts the puzzle still gives its solution
+        assert_eq!(
+            puzzle.solve_warm(&Grid::generate_filled(1)),
+            Ok(solution.clone())
+        );
+        assert_eq!(puzzle.solve_warm(&Grid::empty()), Ok(solution));
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert_eq!(
+            contradictory.solve_warm(&Grid::generate_filled(1)),
+            Err(SolveError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn test_solve_to_vec() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let digits = grid.solve_to_vec().unwrap();
+        assert_eq!(digits.len(), 81);
+        assert_eq!(&digits[..9], &[5, 3, 4, 6, 7, 8, 9, 1, 2]);
+        assert_eq!(digits, grid.clone().solve().unwrap().to_array().concat());
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert_eq!(contradictory.solve_to_vec(), Err(SolveError::Unsatisfiable));
+    }
+
+    #[test]
+    fn test_overlay() {
+        let base: Grid = format!("53{}", " ".repeat(79)).parse().unwrap();
+        let layer: Grid = format!("5 4{}9", " ".repeat(77)).parse().unwrap();
+
+        let merged = base.overlay(&layer).unwrap();
+        let expected: Grid = format!("534{}9", " ".repeat(77)).parse().unwrap();
+        assert_eq!(merged, expected);
+        assert_eq!(merged.get(3, 0), None);
+
+        let conflicting: Grid = format!(" 1{}", " ".repeat(79)).parse().unwrap();
+        assert!(base.overlay(&conflicting).is_err());
+    }
+
+    #[test]
+    fn test_reduce_to_minimal() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        assert!(grid.has_unique_solution());
+        let solution = grid.solve().unwrap();
+        assert!(!solution.is_minimal().unwrap());
+
+        let reduced = solution.reduce_to_minimal(42);
+        assert!(reduced.is_minimal().unwrap());
+        assert_eq!(reduced.clone().solve().unwrap(), solution);
+        assert_eq!(solution.reduce_to_minimal(42), reduced);
+    }
+
+    #[test]
+    fn test_minimal_clue_puzzle() {
+        let solution = Grid::generate_filled(12);
+        let puzzle = solution.minimal_clue_puzzle(3);
+        assert!(puzzle.is_minimal().unwrap());
+        assert_eq!(puzzle.clone().solve(), Ok(solution.clone()));
+
+        let partial = solution.keep_only(&[(0, 0)]);
+        assert_eq!(partial.minimal_clue_puzzle(3), partial);
+    }
+
+    #[test]
+    fn test_candidate_histogram() {
+        // Propagation solves this puzzle completely
+        let easy: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let mut expected = [0; 10];
+        expected[1] = 51;
+        assert_eq!(easy.candidate_histogram(), expected);
+
+        let hard: Grid =
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  "
+                .parse()
+                .unwrap();
+        let histogram = hard.candidate_histogram();
+        assert_eq!(histogram.iter().sum::<usize>(), hard.num_empty());
+        assert_eq!(histogram[0], 0);
+        assert!(histogram[1] < hard.num_empty());
+
+        let mut expected = [0; 10];
+        expected[9] = 81;
+        assert_eq!(Grid::empty().candidate_histogram(), expected);
+
+        // No digit is left for (8, 0)
+        let mut stuck = Grid::empty();
+        for x in 0..8 {
+            stuck[(x, 0)] = Some(x as u8);
+        }
+        stuck[(8, 1)] = Some(8);
+        assert_eq!(stuck.candidate_histogram()[0], 1);
+    }
+
+    #[test]
+    fn test_reveal_fraction() {
+        let solution = Grid::generate_filled(5);
+        let revealed = Grid::empty().reveal_fraction(&solution, 0.25, 1);
+        assert_eq!(revealed.clue_count(), 20);
+        assert!(revealed
+            .filled_cells()
+            .all(|(x, y, d)| solution.get(x, y) == Some(d)));
+        assert_eq!(Grid::empty().reveal_fraction(&solution, 0.25, 1), revealed);
+
+        // Cells that are already filled in count towards the fraction
+        let more = revealed.reveal_fraction(&solution, 0.5, 2);
+        assert_eq!(more.clue_count(), 41);
+        assert!(revealed
+            .filled_cells()
+            .all(|(x, y, d)| more.get(x, y) == Some(d)));
+        assert_eq!(more.reveal_fraction(&solution, 0.1, 3), more);
+        assert_eq!(Grid::empty().reveal_fraction(&solution, 1.0, 4), solution);
+    }
+
+    #[test]
+    fn test_complement() {
+        let puzzle: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let solution = puzzle.clone().solve().unwrap();
+        let complement = puzzle.complement(&solution);
+        for (x, y) in (0..81).map(|i| (i % 9, i / 9)) {
+            match puzzle.get(x, y) {
+                Some(_) => assert_eq!(complement.get(x, y), None),
+                None => assert_eq!(complement.get(x, y), solution.get(x, y)),
+            }
+        }
+        assert_eq!(complement.clue_count(), 81 - puzzle.clue_count());
+        assert_eq!(complement.complement(&solution), puzzle);
+        assert_eq!(solution.complement(&solution), Grid::empty());
+    }
+
+    #[test]
+    fn test_solve_cell() {
+        let puzzle: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        // The solution starts with 534678912, and the digits are 0-based
+        assert_eq!(puzzle.solve_cell(2, 0), Ok(3));
+        assert_eq!(puzzle.solve_cell(8, 0), Ok(1));
+        assert_eq!(puzzle.solve_cell(0, 0), Ok(4));
+        assert!(matches!(
+            puzzle.solve_cell(9, 0),
+            Err(SolveError::InvalidConstraint(_))
+        ));
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert_eq!(
+            contradictory.solve_cell(5, 5),
+            Err(SolveError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn test_determined_cells() {
+        let solution: Grid =
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+                .parse()
+                .unwrap();
+
+        // The 6s and 7s at (3, 0), (4, 0), (3, 3) and (4, 3) can be swapped
+        let mut grid = solution.clone();
+        for (x, y) in [(3, 0), (4, 0), (3, 3), (4, 3), (0, 8), (8, 8)] {
+            grid[(x, y)] = None;
+        }
+        assert_eq!(grid.count_solutions(usize::MAX).unwrap(), 2);
+        assert_eq!(grid.determined_cells().unwrap(), vec![(0, 8, 2), (8, 8, 8)]);
+
+        let unique: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            unique.determined_cells().unwrap().len(),
+            81 - unique.clue_count()
+        );
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert!(contradictory.determined_cells().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_display_errors() {
+        let messages = [
+            (SolveError::Unsatisfiable, "puzzle has no solution"),
+            (
+                SolveError::Parse("invalid character 'x'".to_string()),
+                "failed to parse grid: invalid character 'x'",
+            ),
+            (
+                SolveError::InvalidConstraint("empty cage".to_string()),
+                "invalid constraint: empty cage",
+            ),
+            (
+                SolveError::InternalInconsistency("duplicate digit".to_string()),
+                "internal inconsistency: duplicate digit",
+            ),
+            (
+                SolveError::Proof("permission denied".to_string()),
+                "failed to write proof: permission denied",
+            ),
+            (SolveError::Cancelled, "solve was cancelled"),
+        ];
+        for (error, message) in messages {
+            assert_eq!(error.to_string(), message);
+        }
+
+        assert_eq!(SolutionStatus::None.to_string(), "no solution");
+        assert_eq!(SolutionStatus::Unique.to_string(), "unique solution");
+        assert_eq!(SolutionStatus::Multiple.to_string(), "multiple solutions");
+    }
+
+    #[test]
+    fn test_to_array() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let rows = grid.to_array();
+        assert_eq!(rows[0], [5, 3, 0, 0, 7, 0, 0, 0, 0]);
+        assert_eq!(rows[8], [0, 0, 0, 0, 8, 0, 0, 7, 9]);
+        assert_eq!(Grid::from_array(&rows), Ok(grid));
+
+        let mut invalid = rows;
+        invalid[1][2] = 10;
+        assert_eq!(
+            Grid::from_array(&invalid),
+            Err("invalid cell value 10 at (2, 1)".to_string())
+        );
+    }
+
+    #[test]
+    fn test_to_coords() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let coords = grid.to_coords();
+        assert_eq!(coords.len(), grid.clue_count());
+        assert_eq!(coords[..3], [(0, 0, 5), (1, 0, 3), (4, 0, 7)]);
+        assert_eq!(Grid::from_coords(&coords), Ok(grid));
+        assert_eq!(Grid::from_coords(&[]), Ok(Grid::empty()));
+
+        assert_eq!(
+            Grid::from_coords(&[(9, 0, 1)]),
+            Err("cell (9, 0) is outside the grid".to_string())
+        );
+        assert_eq!(
+            Grid::from_coords(&[(2, 1, 0)]),
+            Err("invalid cell value 0 at (2, 1)".to_string())
+        );
+        assert_eq!(
+            Grid::from_coords(&[(2, 1, 4), (2, 1, 5)]),
+            Err("cell (2, 1) is given more than one digit".to_string())
+        );
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        assert_eq!(grid.edit_distance(&grid), 0);
+        assert_eq!(grid.edit_distance(&Grid::empty()), grid.clue_count());
+
+        // Add a clue, remove one and change one
+        let mut other = grid.clone();
+        other[(2, 0)] = Some(3);
+        other[(0, 0)] = None;
+        other[(1, 0)] = Some(0);
+        assert_eq!(grid.edit_distance(&other), 3);
+        assert_eq!(other.edit_distance(&grid), 3);
+    }
+
+    #[test]
+    fn test_from_str_lenient() {
+        let line =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79";
+        let expected = Grid::from_str(line).unwrap();
+        let full_width = line
+            .chars()
+            .map(|c| match c {
+                '1'..='9' => char::from_u32(c as u32 - '1' as u32 + 0xff11).unwrap(),
+                _ => '\u{3000}',
+            })
+            .collect::<String>();
+        assert_eq!(Grid::from_str_lenient(&full_width), Ok(expected.clone()));
+        assert_eq!(
+            Grid::from_str_lenient(&line.replacen(' ', "\u{a0}", 5)),
+            Ok(expected)
+        );
+        assert!(Grid::from_str_lenient(&line.replacen('5', "x", 1)).is_err());
+
+        assert_eq!(
+            Grid::from_str(&full_width).unwrap_err(),
+            "invalid character '５' (U+FF15), a full-width digit; use Grid::from_str_lenient to \
+             accept it"
+        );
+        assert_eq!(
+            Grid::from_str(&line.replacen(' ', "\u{a0}", 1)).unwrap_err(),
+            "invalid character '\u{a0}' (U+00A0)"
+        );
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let style = DisplayStyle {
+            empty: '.',
+            ..DisplayStyle::default()
+        };
+        let text = grid.format_with(&style);
+        assert_eq!(Grid::from_reader(Cursor::new(&text)), Ok(grid.clone()));
+
+        // Only the first grid is read
+        let line = grid.to_line().replace(' ', "0");
+        let mut reader = Cursor::new(format!("{line}\n{line}\n"));
+        assert_eq!(Grid::from_reader(&mut reader), Ok(grid.clone()));
+        assert_eq!(Grid::from_reader(&mut reader), Ok(grid.clone()));
+
+        assert_eq!(
+            Grid::from_reader(Cursor::new(&text[..100])),
+            Err("expected 81 cells, got 31".to_string())
+        );
+        assert_eq!(
+            Grid::from_reader(Cursor::new("12x")),
+            Err("invalid character 'x'".to_string())
+        );
+        assert!(Grid::from_reader(Cursor::new(format!("{line}1"))).is_err());
+
+        // The line format with spaces for empty cells, with or without a line break
+        assert_eq!(
+            Grid::from_reader(grid.to_line().as_bytes()),
+            Ok(grid.clone())
+        );
+        let text = format!("{}\r\n", grid.to_line());
+        assert_eq!(Grid::from_reader(text.as_bytes()), Ok(grid));
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let lines = [
+            "53  7    ",
+            "6  195",
+            " 98    6 \t",
+            "8   6   3  ",
+            "4  8 3  1",
+            "7   2   6",
+            " 6    28 ",
+            "   419  5   ",
+            "    8  79",
+        ];
+        assert_eq!(Grid::from_str(&lines.join("\n")), Ok(grid.clone()));
+        assert_eq!(
+            Grid::from_str(&(lines.join("\r\n") + "\r\n")),
+            Ok(grid.clone())
+        );
+
+        // A single line with the line break it was read with is not a grid of nine lines
+        let line = grid.to_line();
+        assert_eq!(Grid::from_str(&format!("{line}\n")), Ok(grid.clone()));
+        assert_eq!(Grid::from_str(&format!("{line}\r\n")), Ok(grid));
+        assert!(Grid::from_str(&format!("{line}\n\n")).is_err());
+
+        assert!(Grid::from_str(&lines[..8].join("\n")).is_err());
+        let mut long = lines;
+        long[2] = " 98    6 1";
+        assert_eq!(
+            Grid::from_str(&long.join("\n")),
+            Err("line 3 has 10 cells instead of 9".to_string())
+        );
+    }
+
+    #[test]
+    fn test_fill_pattern() {
+        let puzzle: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        // The clues of the puzzle and the rest of its middle band
+        let pattern = puzzle
+            .cells()
+            .filter(|&((_, y), cell)| cell.is_some() || (3..6).contains(&y))
+            .map(|(position, _)| position)
+            .collect::<Vec<_>>();
+
+        let filled = Grid::fill_pattern(&pattern, 3).unwrap();
+        assert!(filled.has_unique_solution());
+        assert_eq!(
+            filled
+                .filled_cells()
+                .map(|(x, y, _)| (x, y))
+                .collect::<Vec<_>>(),
+            pattern
+        );
+        assert_eq!(Grid::fill_pattern(&pattern, 3), Some(filled));
+
+        assert_eq!(Grid::fill_pattern(&pattern[..16], 3), None);
+    }
+
+    #[test]
+    fn test_safe_fill() {
+        let solution: Grid =
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+                .parse()
+                .unwrap();
+
+        // The 6s and 7s at (3, 0), (4, 0), (3, 3) and (4, 3) can be swapped
+        let ambiguous = [(3, 0), (4, 0), (3, 3), (4, 3)];
+        let mut grid = solution.clone();
+        for (x, y) in ambiguous.into_iter().chain([(0, 8), (8, 8)]) {
+            grid[(x, y)] = None;
+        }
+        let filled = grid.safe_fill().unwrap();
+        assert_eq!(filled.get(0, 8), solution.get(0, 8));
+        assert_eq!(filled.get(8, 8), solution.get(8, 8));
+        assert_eq!(filled.empty_cells().collect::<Vec<_>>(), ambiguous);
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert_eq!(contradictory.safe_fill().unwrap(), contradictory);
+    }
+
+    /// Fails every step, as a proof writer that runs out of disk space would.
+    struct FailingProcessor;
+
+    impl varisat::checker::ProofProcessor for FailingProcessor {
+        fn process_step(
+            &mut self,
+            _: &varisat::checker::CheckedProofStep,
+            _: varisat::checker::CheckerData,
+        ) -> Result<(), anyhow::Error> {
+            Err(anyhow::anyhow!("disk full"))
+        }
+    }
+
+    #[test]
+    fn test_solver_failure() {
+        // A failing solver used to panic instead of returning an error
+        let mut processor = FailingProcessor;
+        let mut solver = Solver::new();
+        solver.add_proof_processor(&mut processor);
+        Grid::empty().add_formula_to(&mut solver, &SolveOptions::default());
+        assert!(matches!(
+            count_models(&mut solver, 2),
+            Err(SolveError::InternalInconsistency(_))
+        ));
+    }
+
+    #[test]
+    fn test_clues_to_unique() {
+        let puzzle: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let solution = puzzle.clone().solve().unwrap();
+        assert!(puzzle.clues_to_unique(&solution).unwrap().is_empty());
+
+        let mut ambiguous = puzzle.clone();
+        for (x, y, _) in puzzle.filled_cells().take(6) {
+            ambiguous[(x, y)] = None;
+        }
+        assert!(!ambiguous.has_unique_solution());
+
+        let clues = ambiguous.clues_to_unique(&solution).unwrap();
+        assert!(!clues.is_empty());
+        let mut completed = ambiguous.clone();
+        for &(x, y, d) in &clues {
+            assert_eq!(solution.get(x, y), Some(d));
+            completed[(x, y)] = Some(d);
+        }
+        assert!(completed.has_unique_solution());
+        assert_eq!(completed.solve().unwrap(), solution);
+
+        assert!(matches!(
+            ambiguous.clues_to_unique(&Grid::generate_filled(1)),
+            Err(SolveError::InvalidConstraint(_))
+        ));
+    }
+
+    #[test]
+    fn test_solve_in() {
+        let puzzles = [
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79",
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  ",
+            "11",
+            "4 6  25  7      8  283       7 4   5 4     3 5   2 1       691  9      7  59  8 6",
+        ];
+        let mut base = Grid::base_solver();
+        for puzzle in puzzles {
+            let puzzle: Grid = format!("{puzzle:81}").parse().unwrap();
+            assert_eq!(puzzle.solve_in(&mut base), puzzle.clone().solve());
+        }
+
+        // A solver without the rules is caught
+        assert!(matches!(
+            Grid::empty().solve_in(&mut Solver::new()),
+            Err(SolveError::InternalInconsistency(_))
+        ));
+    }
+
+    #[test]
+    fn test_solutions() {
+        // The 6s and 7s at (3, 0), (4, 0), (3, 3) and (4, 3) can be swapped
+        let mut grid: Grid =
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+                .parse()
+                .unwrap();
+        for (x, y) in [(3, 0), (4, 0), (3, 3), (4, 3)] {
+            grid[(x, y)] = None;
+        }
+        let all = grid.solutions(2).unwrap();
+        assert_eq!(all.grids.len(), 2);
+        assert!(!all.truncated);
+        let one = grid.solutions(1).unwrap();
+        assert_eq!((one.grids.len(), one.truncated), (1, true));
+
+        // The enumeration of an empty grid stops at the limit
+        let empty = Grid::empty().solutions(20).unwrap();
+        assert_eq!(empty.grids.len(), 20);
+        assert!(empty.truncated);
+        assert!(empty.grids.iter().all(is_valid_solution));
+        assert_eq!(Grid::empty().count_solutions(20).unwrap(), 20);
+        let entropy = Grid::empty().cell_entropy(20).unwrap();
+        assert!(entropy.truncated);
+        assert!(entropy.entropy.iter().any(|&e| e > 0.0));
+        assert!(Grid::empty().determined_cells().unwrap().is_empty());
+        assert_eq!(Grid::empty().solutions(0).unwrap().grids, Vec::new());
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        let none = contradictory.solutions(5).unwrap();
+        assert!(none.grids.is_empty() && !none.truncated);
+    }
+
+    #[test]
+    fn test_cell_entropy() {
+        // The 6s and 7s at (3, 0), (4, 0), (3, 3) and (4, 3) can be swapped
+        let mut grid: Grid =
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+                .parse()
+                .unwrap();
+        let ambiguous = [(3, 0), (4, 0), (3, 3), (4, 3)];
+        for (x, y) in ambiguous {
+            grid[(x, y)] = None;
+        }
+        let entropy = grid.cell_entropy(10).unwrap();
+        assert!(!entropy.truncated);
+        for (i, &e) in entropy.entropy.iter().enumerate() {
+            if ambiguous.contains(&(i % 9, i / 9)) {
+                assert_eq!(e, 1.0);
+            } else {
+                assert_eq!(e, 0.0);
+            }
+        }
+
+        // A single sample determines every cell, but leaves the other solution out
+        let entropy = grid.cell_entropy(1).unwrap();
+        assert_eq!(entropy.entropy, [0.0; 81]);
+        assert!(entropy.truncated);
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        let entropy = contradictory.cell_entropy(10).unwrap();
+        assert_eq!((entropy.entropy, entropy.truncated), ([0.0; 81], false));
+    }
+
+    #[test]
+    fn test_for_each_solution() {
+        // The 6s and 7s at (3, 0), (4, 0), (3, 3) and (4, 3) can be swapped
+        let mut grid: Grid =
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+                .parse()
+                .unwrap();
+        for (x, y) in [(3, 0), (4, 0), (3, 3), (4, 3)] {
+            grid[(x, y)] = None;
+        }
+
+        let mut solutions = Vec::new();
+        let count = grid
+            .for_each_solution(10, |solution| {
+                solutions.push(solution.clone());
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(count, 2);
+        assert_ne!(solutions[0], solutions[1]);
+        assert!(solutions.iter().all(is_valid_solution));
+
+        // Breaking stops the enumeration right away
+        let mut calls = 0;
+        let count = grid
+            .for_each_solution(10, |_| {
+                calls += 1;
+                ControlFlow::Break(())
+            })
+            .unwrap();
+        assert_eq!((count, calls), (1, 1));
+        assert_eq!(
+            grid.for_each_solution(1, |_| ControlFlow::Continue(())),
+            Ok(1)
+        );
+        assert_eq!(
+            Grid::empty().for_each_solution(5, |_| ControlFlow::Continue(())),
+            Ok(5)
+        );
+    }
+
+    #[test]
+    fn test_count_solutions_exact() {
+        let puzzle: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        assert_eq!(puzzle.count_solutions_exact(), Some(1));
+
+        let mut ambiguous = puzzle.clone();
+        for (x, y, _) in puzzle.filled_cells().take(5) {
+            ambiguous[(x, y)] = None;
+        }
+        let count = ambiguous.count_solutions(usize::MAX).unwrap();
+        assert!(count > 1);
+        assert_eq!(ambiguous.count_solutions_exact(), Some(count as u64));
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert_eq!(contradictory.count_solutions_exact(), Some(0));
+        assert_eq!(Grid::empty().count_solutions_exact(), None);
+    }
+
+    #[test]
+    fn test_complete_preserving() {
+        let puzzle: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let solution = puzzle.clone().solve().unwrap();
+
+        // Copy part of the solution, but the entry at (2, 0) is a 2 instead of a 4.
+        let mut attempt = Grid::empty();
+        for (x, y) in [(2, 0), (3, 0), (5, 0), (1, 1), (2, 1), (0, 2), (3, 2)] {
+            attempt[(x, y)] = solution.get(x, y);
+        }
+        attempt[(2, 0)] = Some(1);
+        let completed = puzzle.complete_preserving(&attempt).unwrap();
+        assert_eq!(completed, solution);
+
+        // Without clues every consistent entry is kept, only the duplicate 1 goes
+        let mut attempt = Grid::empty();
+        for x in 0..8 {
+            attempt[(x, 0)] = Some(x as u8);
+        }
+        attempt[(8, 0)] = Some(0);
+        let completed = Grid::empty().complete_preserving(&attempt).unwrap();
+        assert!(is_valid_solution(&completed));
+        assert_eq!(completed.row(0)[..8], attempt.row(0)[..8]);
+        assert_eq!(completed.get(8, 0), Some(8));
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert_eq!(
+            contradictory.complete_preserving(&Grid::empty()),
+            Err(SolveError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn test_sequential_encoding() {
+        let sequential = SolveOptions {
+            encoding: Encoding::Sequential,
+            ..Default::default()
+        };
+        let puzzles = [
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79",
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  ",
+        ];
+        for puzzle in puzzles {
+            let grid: Grid = puzzle.parse().unwrap();
+            let (solution, _) = grid.solve_with(&sequential).unwrap();
+            assert_eq!(Ok(solution), grid.clone().solve());
+        }
+
+        // Auxiliary variables come after the cells, so other constraints still work
+        let formula = Grid::empty().formula(&sequential);
+        assert_eq!(formula.var_count(), NUM_VARS + 324 * 8);
+        assert_eq!(formula.len(), 81 + 324 * 23);
+        let killer = SolveOptions {
+            cages: vec![Cage::new(vec![(0, 0), (1, 0)], RegionConstraint::Sum(3))],
+            ..sequential.clone()
+        };
+        let (solution, _) = Grid::empty().solve_with(&killer).unwrap();
+        assert!(is_valid_solution(&solution));
+        assert_eq!(solution.get(0, 0).unwrap() + solution.get(1, 0).unwrap(), 1);
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert!(matches!(
+            contradictory.solve_with(&sequential),
+            Err(SolveError::Unsatisfiable)
+        ));
+    }
+
+    #[test]
+    fn test_break_digit_symmetry() {
+        // Without the 7s, 8s and 9s these can be placed in any order
+        let solution: Grid =
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+                .parse()
+                .unwrap();
+        let mut grid = solution.clone();
+        for i in 0..81 {
+            if grid.cells[i].is_some_and(|d| d >= 6) {
+                grid.cells[i] = None;
+            }
+        }
+        let breaking = SolveOptions {
+            break_digit_symmetry: true,
+            ..Default::default()
+        };
+        let all = grid.count_solutions(1000).unwrap();
+        let representatives = grid.count_solutions_with(&breaking, 1000).unwrap();
+        assert!(all >= 6);
+        assert_eq!(all, representatives * 6);
+
+        // The representative has the unused digits in increasing order of first appearance
+        let (representative, _) = grid.solve_with(&breaking).unwrap();
+        let first = |d| representative.cells.iter().position(|&c| c == Some(d));
+        assert!(first(6) < first(7) && first(7) < first(8));
+
+        let solved = solution.count_solutions_with(&breaking, 10);
+        assert_eq!(solved, Ok(1));
+
+        let parity = SolveOptions {
+            parity: vec![(0, 0, Parity::Even)],
+            ..breaking
+        };
+        assert!(matches!(
+            grid.count_solutions_with(&parity, 10),
+            Err(SolveError::InvalidConstraint(_))
+        ));
+    }
+
+    #[test]
+    fn test_enforce_rules() {
+        // Two rows that share digits within the top left box
+        let grid: Grid = format!("123456789234567891{}", " ".repeat(63))
+            .parse()
+            .unwrap();
+        assert!(matches!(
+            grid.solve_with(&SolveOptions::default()),
+            Err(SolveError::Unsatisfiable)
+        ));
+
+        let options = SolveOptions {
+            enforce_boxes: false,
+            verify: true,
+            ..Default::default()
+        };
+        let (solution, _) = grid.solve_with(&options).unwrap();
+        assert!(!is_valid_solution(&solution));
+        assert!(
+            (0..9).all(|i| has_every_digit(solution.row(i)) && has_every_digit(solution.col(i)))
+        );
+        assert!(grid
+            .filled_cells()
+            .all(|(x, y, d)| solution.get(x, y) == Some(d)));
+
+        // A complete grid is checked against the enforced rules only
+        let latin: Grid = (0..81)
+            .map(|i| char::from(b'1' + ((i % 9 + i / 9) % 9) as u8))
+            .collect::<String>()
+            .parse()
+            .unwrap();
+        assert_eq!(latin.solve_with(&options).unwrap().0, latin);
+        let no_columns = SolveOptions {
+            enforce_boxes: false,
+            enforce_columns: false,
+            ..Default::default()
+        };
+        let rows_only: Grid = "123456789".repeat(9).parse().unwrap();
+        assert_eq!(rows_only.solve_with(&no_columns).unwrap().0, rows_only);
+        assert!(matches!(
+            rows_only.solve_with(&options),
+            Err(SolveError::Unsatisfiable)
+        ));
+    }
+
+    #[test]
+    fn test_parity() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let solution = grid.solve().unwrap();
+
+        // Without the 8s and 9s, swapping them yields another solution.
+        let mut ambiguous = solution.clone();
+        for cell in ambiguous.cells.iter_mut() {
+            if matches!(cell, Some(7 | 8)) {
+                *cell = None;
+            }
+        }
+        assert_eq!(ambiguous.count_solutions(usize::MAX).unwrap(), 2);
+
+        // Requiring one of the 8s to be even rules out the swap.
+        let i = solution.cells.iter().position(|&c| c == Some(7)).unwrap();
+        let options = SolveOptions {
+            parity: vec![(i % 9, i / 9, Parity::Even)],
+            ..Default::default()
+        };
+        assert_eq!(
+            count_models(&mut ambiguous.solver_with(&options), usize::MAX).unwrap(),
+            1
+        );
+        assert_eq!(ambiguous.solve_with(&options).unwrap().0, solution);
+    }
+
+    #[test]
+    fn test_thermometer() {
+        // A full-length thermometer leaves only one way to fill its cells
+        let options = SolveOptions {
+            thermometers: vec![Thermometer((0..9).map(|i| (8 - i, 4)).collect())],
+            ..Default::default()
+        };
+        let (solution, _) = Grid::empty().solve_with(&options).unwrap();
+        assert_eq!(solution.row(4), std::array::from_fn(|x| Some(8 - x as u8)));
+
+        // A bulb of 9 cannot increase
+        let mut grid = Grid::empty();
+        grid[(0, 0)] = Some(8);
+        let options = SolveOptions {
+            thermometers: vec![Thermometer(vec![(0, 0), (1, 1)])],
+            ..Default::default()
+        };
+        assert_eq!(
+            grid.solve_with(&options).err(),
+            Some(SolveError::Unsatisfiable)
+        );
+
+        let options = SolveOptions {
+            thermometers: vec![Thermometer(vec![(0, 0), (9, 0)])],
+            ..Default::default()
+        };
+        assert!(matches!(
+            Grid::empty().solve_with(&options),
+            Err(SolveError::InvalidConstraint(_))
+        ));
+    }
+
+    #[test]
+    fn test_clone_region() {
+        // The clue in the top left box is copied to the bottom right one
+        let mut grid = Grid::empty();
+        grid[(1, 2)] = Some(4);
+        let options = SolveOptions {
+            clones: vec![CloneRegion(box_cells(0).to_vec(), box_cells(8).to_vec())],
+            preprocess: true,
+            ..Default::default()
+        };
+        let (solution, stats) = grid.solve_with(&options).unwrap();
+        assert_eq!(stats.preprocessed_cells, 2);
+        assert_eq!(solution.get(7, 8), Some(4));
+        assert_eq!(solution.box_cells(0), solution.box_cells(8));
+
+        // Two cells in the same row cannot hold the same digit
+        let options = SolveOptions {
+            clones: vec![CloneRegion(vec![(0, 0)], vec![(5, 0)])],
+            ..Default::default()
+        };
+        assert_eq!(
+            Grid::empty().solve_with(&options).err(),
+            Some(SolveError::Unsatisfiable)
+        );
+
+        let options = SolveOptions {
+            clones: vec![CloneRegion(vec![(0, 0), (1, 0)], vec![(5, 5)])],
+            ..Default::default()
+        };
+        assert!(matches!(
+            Grid::empty().solve_with(&options),
+            Err(SolveError::InvalidConstraint(_))
+        ));
+    }
+
+    #[test]
+    fn test_killer_combinations() {
+        // 1 + 2
+        assert_eq!(killer_combinations(2, 3), [0b11]);
+        // 4 + 6, 3 + 7, 2 + 8 and 1 + 9
+        let mut masks = killer_combinations(2, 10).to_vec();
+        masks.sort();
+        assert_eq!(
+            masks,
+            [0b0_0010_1000, 0b0_0100_0100, 0b0_1000_0010, 0b1_0000_0001]
+        );
+        // 7 + 8 + 9
+        assert_eq!(killer_combinations(3, 24), [0b1_1100_0000]);
+        assert_eq!(killer_combinations(9, 45), [0b1_1111_1111]);
+        assert!(killer_combinations(3, 5).is_empty());
+        assert!(killer_combinations(10, 45).is_empty());
+        assert!(killer_combinations(2, 100).is_empty());
+    }
+
+    #[test]
+    fn test_cages() {
+        // Only 1 and 2 add up to 3 without repeating a digit
+        let options = SolveOptions {
+            cages: vec![
+                Cage::new(vec![(0, 0), (1, 0)], RegionConstraint::Sum(3)),
+                Cage::new(vec![(0, 0), (1, 0)], RegionConstraint::Unique),
+            ],
+            ..Default::default()
+        };
+        let (solution, _) = Grid::empty().solve_with(&options).unwrap();
+        let mut digits = [solution.get(0, 0).unwrap(), solution.get(1, 0).unwrap()];
+        digits.sort();
+        assert_eq!(digits, [0, 1]);
+
+        // Repeated digits are allowed in different boxes, rows and columns
+        let options = SolveOptions {
+            cages: vec![Cage::new(vec![(0, 0), (4, 4)], RegionConstraint::Sum(2))],
+            ..Default::default()
+        };
+        let (solution, _) = Grid::empty().solve_with(&options).unwrap();
+        assert_eq!((solution.get(0, 0), solution.get(4, 4)), (Some(0), Some(0)));
+
+        // Only 8 and 9 multiply to 72
+        let options = SolveOptions {
+            cages: vec![Cage::new(
+                vec![(0, 0), (1, 0)],
+                RegionConstraint::Product(72),
+            )],
+            ..Default::default()
+        };
+        let (solution, _) = Grid::empty().solve_with(&options).unwrap();
+        let mut digits = [solution.get(0, 0).unwrap(), solution.get(1, 0).unwrap()];
+        digits.sort();
+        assert_eq!(digits, [7, 8]);
+
+        // The auxiliary variables do not count as separate solutions
+        let solution: Grid =
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+                .parse()
+                .unwrap();
+        let mut grid = solution.clone();
+        for (x, y) in [(3, 0), (4, 0), (3, 3), (4, 3)] {
+            grid[(x, y)] = None;
+        }
+        let options = SolveOptions {
+            cages: vec![Cage::new(
+                vec![(3, 0), (4, 0)],
+                RegionConstraint::Product(42),
+            )],
+            ..Default::default()
+        };
+        assert_eq!(
+            count_models(&mut grid.solver_with(&options), usize::MAX).unwrap(),
+            2
+        );
+
+        // A sum that cannot be reached
+        let options = SolveOptions {
+            cages: vec![Cage::new(vec![(0, 0), (1, 0)], RegionConstraint::Sum(19))],
+            ..Default::default()
+        };
+        assert_eq!(
+            Grid::empty().solve_with(&options).err(),
+            Some(SolveError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn test_edge_clues() {
+        // Next to a 2, only a 1 adds up to 3
+        let grid = Grid::empty().with_clue(0, 0, 1);
+        let options = SolveOptions {
+            edge_clues: vec![EdgeClue::new((0, 0), (1, 0), EdgeRelation::Sum(3))],
+            ..Default::default()
+        };
+        let (solution, _) = grid.solve_with(&options).unwrap();
+        assert!(is_valid_solution(&solution));
+        assert_eq!(solution.get(1, 0), Some(0));
+
+        // Only 1 and 9 differ by 8, and 9 is neither double nor half a digit, so the 1 comes
+        // second, followed by a 2 and an 8
+        let options = SolveOptions {
+            edge_clues: vec![
+                EdgeClue::new((0, 0), (0, 1), EdgeRelation::Diff(8)),
+                EdgeClue::new((0, 1), (0, 2), EdgeRelation::Ratio(2)),
+                EdgeClue::new((0, 2), (1, 2), EdgeRelation::Ratio(4)),
+            ],
+            ..Default::default()
+        };
+        let (solution, _) = Grid::empty().solve_with(&options).unwrap();
+        let digit = |x, y| solution.get(x, y).unwrap() + 1;
+        assert_eq!((digit(0, 0), digit(0, 1), digit(0, 2)), (9, 1, 2));
+        assert_eq!(digit(1, 2), 8);
+
+        let options = SolveOptions {
+            edge_clues: vec![EdgeClue::new((0, 0), (0, 0), EdgeRelation::Sum(2))],
+            ..Default::default()
+        };
+        assert!(matches!(
+            Grid::empty().solve_with(&options),
+            Err(SolveError::InvalidConstraint(_))
+        ));
+    }
+
+    #[test]
+    fn test_solve_str() {
+        assert_eq!(
+            solve_str(
+                "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+            ),
+            Ok(
+                "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+                    .to_string()
+            )
+        );
+        assert!(matches!(solve_str("53  x"), Err(SolveError::Parse(_))));
+        assert_eq!(
+            solve_str(&format!("11{}", " ".repeat(79))),
+            Err(SolveError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn test_solve_time() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        let (_, stats) = grid.solve_with(&SolveOptions::default()).unwrap();
+        assert!(stats.solve_time > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_disjoint_groups() {
+        let grid: Grid =
+            "                9         5     8      1   63  8 6 412  4 2          65  876  3 1"
+                .parse()
+                .unwrap();
+        assert_eq!(grid.count_solutions(2).unwrap(), 2);
+
+        let options = SolveOptions {
+            disjoint_groups: true,
+            ..Default::default()
+        };
+        assert_eq!(count_models(&mut grid.solver_with(&options), 2).unwrap(), 1);
+
+        let (solution, _) = grid.solve_with(&options).unwrap();
+        for i in 0..9 {
+            let mut digits = (0..9)
+                .map(|b| {
+                    let (x, y) = box_cells(b)[i];
+                    solution.get(x, y).unwrap()
+                })
+                .collect::<Vec<_>>();
+            digits.sort();
+            assert_eq!(digits, (0..9).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_filled_cells() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        assert_eq!(grid.filled_cells().count(), grid.clue_count());
+        assert_eq!(grid.filled_cells().count(), 30);
+        assert_eq!(grid.filled_cells().next(), Some((0, 0, 4)));
+        assert!(grid
+            .filled_cells()
+            .all(|(x, y, _)| !grid.is_empty_cell(x, y)));
+        assert!(grid.is_empty_cell(2, 0));
+    }
+
+    #[test]
+    fn test_extra_regions() {
+        let grid: Grid =
+            "                9         5     8      1   63  8 6 412  4 2          65  876  3 1"
+                .parse()
+                .unwrap();
+        let options = SolveOptions {
+            extra_regions: (0..9).map(ExtraRegion::disjoint_group).collect(),
+            ..Default::default()
+        };
+        assert_eq!(count_models(&mut grid.solver_with(&options), 2).unwrap(), 1);
+
+        assert_eq!(ExtraRegion::window(1).cells[0], (5, 1));
+        assert_eq!(ExtraRegion::window(3).cells[8], (7, 7));
+        assert_eq!(ExtraRegion::anti_diagonal().cells[0], (8, 0));
+
+        // A custom region in the shape of a plus sign
+        let plus = ExtraRegion::new(vec![
+            (4, 2),
+            (4, 3),
+            (2, 4),
+            (3, 4),
+            (4, 4),
+            (5, 4),
+            (6, 4),
+            (4, 5),
+            (4, 6),
+        ]);
+        let options = SolveOptions {
+            extra_regions: vec![plus.clone(), ExtraRegion::main_diagonal()],
+            ..Default::default()
+        };
+        let (solution, _) = Grid::empty().solve_with(&options).unwrap();
+        for region in &options.extra_regions {
+            let mut digits = region
+                .cells
+                .iter()
+                .map(|&(x, y)| solution.get(x, y).unwrap())
+                .collect::<Vec<_>>();
+            digits.sort();
+            assert_eq!(digits, (0..9).collect::<Vec<_>>());
+        }
+
+        let mut invalid = plus.clone();
+        invalid.cells.pop();
+        let options = SolveOptions {
+            extra_regions: vec![invalid],
+            ..Default::default()
+        };
+        assert!(matches!(
+            Grid::empty().solve_with(&options),
+            Err(SolveError::InvalidConstraint(_))
+        ));
+
+        let mut invalid = plus;
+        invalid.cells[0] = invalid.cells[1];
+        let options = SolveOptions {
+            extra_regions: vec![invalid],
+            ..Default::default()
+        };
+        assert!(matches!(
+            Grid::empty().solve_with(&options),
+            Err(SolveError::InvalidConstraint(_))
+        ));
+    }
+
+    #[test]
+    fn test_solve_biased() {
+        let grid: Grid =
+            "                9         5     8      1   63  8 6 412  4 2          65  876  3 1"
+                .parse()
+                .unwrap();
+
+        let canonical = grid.solve_canonical().unwrap();
+        assert_eq!(grid.solve_biased(&canonical).unwrap(), canonical);
+
+        let options = SolveOptions {
+            disjoint_groups: true,
+            ..Default::default()
+        };
+        let (other, _) = grid.solve_with(&options).unwrap();
+        assert_ne!(other, canonical);
+        assert_eq!(grid.solve_biased(&other).unwrap(), other);
+
+        // A template cell that contradicts the 9 at (7, 1) is dropped, the others are honored.
+        let mut template = other.clone();
+        template[(8, 1)] = Some(8);
+        let biased = grid.solve_biased(&template).unwrap();
+        assert_eq!(biased, other);
+    }
+
+    #[test]
+    fn test_formula_complete_grid() {
+        let solution: Grid =
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+                .parse()
+                .unwrap();
+
+        // A valid complete grid is the one and only model
+        assert_eq!(solution.count_solutions(usize::MAX).unwrap(), 1);
+        assert_eq!(solution.clone().solve().unwrap(), solution);
+
+        // Swapping two digits within a row breaks the columns
+        let mut invalid = solution.clone();
+        invalid.cells.swap(0, 1);
+        assert_eq!(invalid.count_solutions(usize::MAX).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_units() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let digits = |cells: [Cell; 9]| cells.map(|c| c.map(|d| d + 1));
+
+        assert_eq!(
+            digits(grid.row(1)),
+            [
+                Some(6),
+                None,
+                None,
+                Some(1),
+                Some(9),
+                Some(5),
+                None,
+                None,
+                None
+            ]
+        );
+        assert_eq!(
+            digits(grid.col(0)),
+            [
+                Some(5),
+                Some(6),
+                None,
+                Some(8),
+                Some(4),
+                Some(7),
+                None,
+                None,
+                None
+            ]
+        );
+        assert_eq!(
+            digits(grid.box_cells(8)),
+            [
+                Some(2),
+                Some(8),
+                None,
+                None,
+                None,
+                Some(5),
+                None,
+                Some(7),
+                Some(9)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_solve_excluding() {
+        let solution: Grid =
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+                .parse()
+                .unwrap();
+
+        // Without the 8s and 9s, swapping them yields the only other solution.
+        let mut ambiguous = solution.clone();
+        for cell in ambiguous.cells.iter_mut() {
+            if matches!(cell, Some(7 | 8)) {
+                *cell = None;
+            }
+        }
+
+        let other = ambiguous.solve_excluding(&solution).unwrap();
+        assert_ne!(other, solution);
+        assert_eq!(
+            other
+                .to_line()
+                .replace('8', "x")
+                .replace('9', "8")
+                .replace('x', "9"),
+            solution.to_line()
+        );
+        assert_eq!(ambiguous.solve_excluding(&other).unwrap(), solution);
+
+        assert_eq!(
+            solution.solve_excluding(&solution),
+            Err(SolveError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn test_candidates() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        let candidates = grid.candidates();
+        let digits = |i: usize| {
+            (0..9)
+                .filter(|&d| candidates[i][d])
+                .map(|d| d + 1)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(digits(0), vec![5]);
+        assert_eq!(digits(2), vec![1, 2, 4]);
+        assert_eq!(digits(80), vec![9]);
+    }
+
+    #[test]
+    fn test_check_pencil_marks() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let mut marks = grid.candidates();
+        assert_eq!(grid.check_pencil_marks(&marks).unwrap(), vec![]);
+
+        // Leaving out a digit other than the solution is fine, the solution's 4 is not
+        marks[2][0] = false;
+        assert_eq!(grid.check_pencil_marks(&marks).unwrap(), vec![]);
+        marks[2][3] = false;
+        // A 5 already appears in the first row
+        marks[3][4] = true;
+        // The marks of the clues do not matter
+        marks[0] = [false; 9];
+        assert_eq!(
+            grid.check_pencil_marks(&marks).unwrap(),
+            vec![(2, 0), (3, 0)]
+        );
+
+        // Without a unique solution only eliminated digits are wrong
+        let mut marks = [[false; 9]; 81];
+        assert_eq!(Grid::empty().check_pencil_marks(&marks).unwrap(), vec![]);
+        let row: Grid = format!("12{}", " ".repeat(79)).parse().unwrap();
+        marks[2][0] = true;
+        assert_eq!(row.check_pencil_marks(&marks).unwrap(), vec![(2, 0)]);
+    }
+
+    #[test]
+    fn test_most_constrained_cell() {
+        let grid: Grid = format!("12345678{}", " ".repeat(73)).parse().unwrap();
+        assert_eq!(grid.most_constrained_cell(), Some((8, 0)));
+
+        let grid: Grid = " ".repeat(81).parse().unwrap();
+        assert_eq!(grid.most_constrained_cell(), Some((0, 0)));
+
+        let solution = grid.solve().unwrap();
+        assert_eq!(solution.most_constrained_cell(), None);
+    }
+
+    #[test]
+    fn test_generate_filled() {
+        let grid = Grid::generate_filled(7);
+        assert_eq!(grid, Grid::generate_filled(7));
+        assert_ne!(grid, Grid::generate_filled(8));
+
+        for grid in [grid, Grid::generate_filled_random()] {
+            assert!(is_valid_solution(&grid));
+        }
+    }
+
+    #[test]
+    fn test_is_valid_solution() {
+        let solution: Grid =
+            "534678912672195348198342567859761423426853791713924856961537284287419635345286179"
+                .parse()
+                .unwrap();
+        assert!(is_valid_solution(&solution));
+
+        let mut duplicate = solution.clone();
+        duplicate[(0, 0)] = Some(2);
+        assert!(!is_valid_solution(&duplicate));
+
+        let mut incomplete = solution.clone();
+        incomplete[(0, 0)] = None;
+        assert!(!is_valid_solution(&incomplete));
+    }
+
+    #[test]
+    fn test_apply_move() {
+        let mut grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        assert_eq!(grid.apply_move(2, 0, 3), Ok(()));
+        assert_eq!(grid.get(2, 0), Some(3));
+
+        let before = grid.clone();
+        assert_eq!(grid.apply_move(0, 0, 0), Err(MoveError::Given));
+        assert_eq!(grid.apply_move(9, 0, 0), Err(MoveError::OutOfBounds));
+        assert_eq!(grid.apply_move(3, 0, 9), Err(MoveError::InvalidDigit));
+        assert_eq!(
+            grid.apply_move(3, 0, 6),
+            Err(MoveError::Conflict { x: 4, y: 0 })
+        );
+        assert_eq!(
+            grid.apply_move(0, 2, 3),
+            Err(MoveError::Conflict { x: 0, y: 4 })
+        );
+        assert_eq!(
+            grid.apply_move(2, 1, 2),
+            Err(MoveError::Conflict { x: 1, y: 0 })
+        );
+        assert_eq!(grid, before);
+    }
+
+    #[test]
+    fn test_solution_status() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        assert_eq!(grid.solution_status().unwrap(), SolutionStatus::Unique);
+
+        let mut contradictory = grid.clone();
+        contradictory[(2, 0)] = Some(0);
+        assert_eq!(
+            contradictory.solution_status().unwrap(),
+            SolutionStatus::None
+        );
+
+        assert_eq!(
+            Grid::empty().solution_status().unwrap(),
+            SolutionStatus::Multiple
+        );
+    }
+
+    #[test]
+    fn test_keep_only() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let solution = grid.clone().solve().unwrap();
+
+        let clues = grid
+            .filled_cells()
+            .map(|(x, y, _)| (x, y))
+            .collect::<Vec<_>>();
+        let puzzle = solution.keep_only(&clues);
+        assert_eq!(puzzle, grid);
+        assert_eq!(puzzle.solve().unwrap(), solution);
+
+        assert_eq!(solution.keep_only(&[]), Grid::empty());
+    }
+
+    #[test]
+    fn test_with_clue() {
+        let grid = Grid::empty()
+            .with_clue(0, 0, 4)
+            .with_clue(1, 0, 2)
+            .with_clue(4, 0, 6);
+        assert_eq!(grid.to_line(), format!("53  7{}", " ".repeat(76)));
+
+        let solution = grid.clone().solve().unwrap();
+        assert!(is_valid_solution(&solution));
+        assert_eq!(solution.get(4, 0), Some(6));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_with_clue_out_of_range() {
+        Grid::empty().with_clue(0, 0, 9);
+    }
+
+    #[test]
+    fn test_fill_random_consistent() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        let filled = grid.fill_random_consistent(10, 42).unwrap();
+        assert_eq!(filled.clue_count(), grid.clue_count() + 10);
+        assert!(filled.conflicts().is_empty());
+        assert!(grid
+            .filled_cells()
+            .all(|(x, y, d)| filled.get(x, y) == Some(d)));
+        assert_eq!(grid.fill_random_consistent(10, 42), Some(filled));
+
+        let seeded = Grid::empty().fill_random_consistent(20, 7).unwrap();
+        assert_eq!(seeded.clue_count(), 20);
+        assert!(seeded.conflicts().is_empty());
+
+        assert_eq!(grid.fill_random_consistent(52, 42), None);
+    }
+
+    #[test]
+    fn test_from_ocr() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let cells = std::array::from_fn(|y| grid.row(y).map(|cell| cell.map(|d| d + 1)));
+        let mut confidence = [[0.9; 9]; 9];
+        confidence[0][0] = 0.2;
+        confidence[8][8] = 0.5;
+
+        let recognized = Grid::from_ocr(cells, confidence, 0.5).unwrap();
+        assert_eq!(recognized.get(0, 0), None);
+        assert_eq!(recognized.get(8, 8), Some(8));
+        assert_eq!(recognized.clue_count(), grid.clue_count() - 1);
+        assert_eq!(Grid::from_ocr(cells, confidence, 0.0).unwrap(), grid);
+
+        // Digits are 1-9, so 0 is not an empty cell
+        let mut invalid = cells;
+        invalid[4][4] = Some(0);
+        assert_eq!(
+            Grid::from_ocr(invalid, confidence, 0.5),
+            Err("invalid cell value 0 at (4, 4)".to_string())
+        );
+        // Invalid values are rejected even when their confidence is low
+        invalid[4][4] = Some(10);
+        assert!(Grid::from_ocr(invalid, [[0.0; 9]; 9], 0.5).is_err());
+    }
+
+    #[test]
+    fn test_cells() {
+        let grid: Grid = format!("53{}9", " ".repeat(78)).parse().unwrap();
+
+        let cells = grid.cells().collect::<Vec<_>>();
+        assert_eq!(cells.len(), 81);
+        assert_eq!(cells[0], ((0, 0), Some(4)));
+        assert_eq!(cells[1], ((1, 0), Some(2)));
+        assert_eq!(cells[9], ((0, 1), None));
+        assert_eq!(cells[80], ((8, 8), Some(8)));
+        assert!(cells
+            .iter()
+            .enumerate()
+            .all(|(i, &((x, y), _))| (x, y) == (i % 9, i / 9)));
+    }
+
+    #[test]
+    fn test_empty_cells() {
+        let grid: Grid = format!("53{}9", " ".repeat(78)).parse().unwrap();
+        assert_eq!(grid.num_empty(), 78);
+
+        let empty = grid.empty_cells().collect::<Vec<_>>();
+        assert_eq!(empty.len(), 78);
+        assert_eq!(empty[0], (2, 0));
+        assert_eq!(empty.last(), Some(&(7, 8)));
+        assert!(empty.iter().all(|&(x, y)| grid.is_empty_cell(x, y)));
+
+        assert_eq!(Grid::empty().num_empty(), 81);
+        assert_eq!(Grid::generate_filled(1).empty_cells().count(), 0);
+    }
+
+    #[test]
+    fn test_var_for() {
+        assert_eq!(var_for(0, 0, 0), Var::from_index(0));
+        assert_eq!(var_for(8, 0, 0), Var::from_index(72));
+        assert_eq!(var_for(0, 8, 0), Var::from_index(648));
+        assert_eq!(var_for(8, 8, 8), Var::from_index(NUM_VARS - 1));
+        assert_eq!(
+            Grid::empty().formula(&SolveOptions::default()).var_count(),
+            NUM_VARS
+        );
+    }
+
+    #[test]
+    fn test_cell_for() {
+        for y in 0..9 {
+            for x in 0..9 {
+                for d in 0..9 {
+                    assert_eq!(cell_for(var_for(x, y, d)), (x, y, d));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_box_index() {
+        assert_eq!(box_index(0, 0), 0);
+        assert_eq!(box_index(8, 0), 2);
+        assert_eq!(box_index(4, 4), 4);
+        assert_eq!(box_index(2, 6), 6);
+        assert_eq!(box_index(8, 8), 8);
+
+        assert_eq!(box_cells(0)[..3], [(0, 0), (1, 0), (2, 0)]);
+        assert_eq!(box_cells(5)[4], (7, 4));
+        assert_eq!(box_cells(8)[8], (8, 8));
+        for b in 0..9 {
+            assert!(box_cells(b).iter().all(|&(x, y)| box_index(x, y) == b));
+        }
+    }
+
+    #[test]
+    fn test_box_clue_counts() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        assert_eq!(grid.box_clue_counts(), [5, 4, 1, 3, 4, 3, 1, 4, 5]);
+        assert_eq!(
+            grid.box_clue_counts().iter().sum::<usize>(),
+            grid.clue_count()
+        );
+        assert_eq!(Grid::empty().box_clue_counts(), [0; 9]);
+    }
+
+    #[test]
+    fn test_band_and_stack_clue_counts() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        assert_eq!(grid.band_clue_counts(), [10, 10, 10]);
+        assert_eq!(grid.stack_clue_counts(), [9, 12, 9]);
+
+        let top_left = Grid::empty().with_clue(0, 0, 0).with_clue(8, 2, 1);
+        assert_eq!(top_left.band_clue_counts(), [2, 0, 0]);
+        assert_eq!(top_left.stack_clue_counts(), [1, 0, 1]);
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        assert!(grid.conflicts().is_empty());
+
+        let mut grid = Grid::empty();
+        // Two 5s in the first row
+        grid[(0, 0)] = Some(4);
+        grid[(8, 0)] = Some(4);
+        // Two 9s in the center box
+        grid[(4, 4)] = Some(8);
+        grid[(5, 5)] = Some(8);
+        // Three 3s in the bottom-left box, two of which also share a column
+        grid[(2, 6)] = Some(2);
+        grid[(0, 7)] = Some(2);
+        grid[(2, 8)] = Some(2);
+        assert_eq!(
+            grid.conflicts(),
+            vec![
+                ((0, 0), (8, 0)),
+                ((4, 4), (5, 5)),
+                ((2, 6), (0, 7)),
+                ((2, 6), (2, 8)),
+                ((0, 7), (2, 8)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_clues() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        assert_eq!(grid.validate_clues(), Ok(()));
+
+        let mut grid = Grid::empty();
+        // Two 5s in the first row
+        grid[(0, 0)] = Some(4);
+        grid[(8, 0)] = Some(4);
+        // Two 9s in the center box
+        grid[(4, 4)] = Some(8);
+        grid[(5, 5)] = Some(8);
+        // Two 2s that share both a row and a box
+        grid[(6, 8)] = Some(1);
+        grid[(8, 8)] = Some(1);
+        assert_eq!(
+            grid.validate_clues(),
+            Err(vec![
+                ClueConflict {
+                    cells: vec![(0, 0), (8, 0)],
+                    digit: 4,
+                    unit: Unit::Row(0),
+                },
+                ClueConflict {
+                    cells: vec![(6, 8), (8, 8)],
+                    digit: 1,
+                    unit: Unit::Row(8),
+                },
+                ClueConflict {
+                    cells: vec![(4, 4), (5, 5)],
+                    digit: 8,
+                    unit: Unit::Box(4),
+                },
+                ClueConflict {
+                    cells: vec![(6, 8), (8, 8)],
+                    digit: 1,
+                    unit: Unit::Box(8),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_alphabet() {
+        let alphabet = Alphabet::new("ABCDEFGHI").unwrap();
+        let grid = Grid::from_str_with_alphabet(
+            "EC  G    F  AIE    IH    F H   F   CD  H C  AG   B   F F    BH    DAI  E    H  GI",
+            &alphabet,
+        )
+        .unwrap();
+        let expected: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        assert_eq!(grid, expected);
+
+        let displayed = grid.display_with_alphabet(&alphabet).to_string();
+        assert!(displayed.starts_with("E C   |   G   |"));
+        assert_eq!(
+            displayed.replace(|c| "ABCDEFGHI".contains(c), "x"),
+            grid.to_string().replace(|c| "123456789".contains(c), "x")
+        );
+
+        assert!(Grid::from_str_with_alphabet(&grid.to_line(), &alphabet).is_err());
+        assert!(Alphabet::new("ABCDEFGH").is_err());
+        assert!(Alphabet::new("ABCDEFGHA").is_err());
+        assert!(Alphabet::new("ABCD FGHI").is_err());
+    }
+
+    #[test]
+    fn test_format_with() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        assert_eq!(grid.format_with(&DisplayStyle::default()), grid.to_string());
+
+        let compact = DisplayStyle {
+            separators: false,
+            empty: '.',
+            ..Default::default()
+        };
+        assert_eq!(
+            grid.format_with(&compact),
+            concat!(
+                "53.|.7.|...\n",
+                "6..|195|...\n",
+                ".98|...|.6.\n",
+                "---+---+---\n",
+                "8..|.6.|..3\n",
+                "4..|8.3|..1\n",
+                "7..|.2.|..6\n",
+                "---+---+---\n",
+                ".6.|...|28.\n",
+                "...|419|..5\n",
+                "...|.8.|.79\n",
+            )
+        );
+
+        let columns = DisplayStyle {
+            column_major: true,
+            separators: false,
+            block_lines: false,
+            empty: '0',
+        };
+        assert_eq!(
+            grid.format_with(&columns),
+            concat!(
+                "560847000\n",
+                "309000600\n",
+                "008000000\n",
+                "010080040\n",
+                "790602018\n",
+                "050030090\n",
+                "000000200\n",
+                "006000807\n",
+                "000316059\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_wrong_cell_count() {
+        let grid: Grid = format!("1234{}", " ".repeat(77)).parse().unwrap();
+        assert_eq!(grid.clue_count(), 4);
+
+        assert_eq!(
+            Grid::from_str("1234341221434321"),
+            Err("16 cells make a 4x4 grid, but only 9x9 grids are supported".to_string())
+        );
+        assert_eq!(
+            Grid::from_str(&" ".repeat(256)),
+            Err("256 cells make a 16x16 grid, but only 9x9 grids are supported".to_string())
+        );
+        assert_eq!(
+            Grid::from_str(&" ".repeat(80)),
+            Err(
+                "expected 81 cells, got 80, which is not the size of a grid with square boxes"
+                    .to_string()
+            )
+        );
+        // 36 cells make a 6x6 grid, whose boxes are not square
+        assert!(Grid::from_str(&" ".repeat(36))
+            .unwrap_err()
+            .starts_with("expected 81 cells"));
+    }
+
+    #[test]
+    fn test_empty_glyph() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let line = grid.to_line();
+        assert_eq!(line.matches(Grid::EMPTY).count(), 81 - grid.clue_count());
+        assert_eq!(Grid::from_str(&line), Ok(grid.clone()));
+
+        // Without separators and box lines the display is the line, split into rows
+        let plain = DisplayStyle {
+            separators: false,
+            block_lines: false,
+            ..Default::default()
+        };
+        assert_eq!(DisplayStyle::default().empty, Grid::EMPTY);
+        let text = grid.format_with(&plain);
+        assert_eq!(text.replace('\n', ""), line);
+        assert_eq!(Grid::from_str(&text), Ok(grid.clone()));
+        assert!(grid
+            .to_string()
+            .lines()
+            .next()
+            .unwrap()
+            .starts_with(&format!("5 3 {} |", Grid::EMPTY)));
+    }
+
+    #[test]
+    fn test_format_highlighted() {
+        let grid: Grid = format!("1  1{}", " ".repeat(77)).parse().unwrap();
+        let cells = grid
+            .conflicts()
+            .into_iter()
+            .flat_map(|(a, b)| [a, b])
+            .collect::<Vec<_>>();
+        let style = DisplayStyle {
+            separators: false,
+            empty: '.',
+            ..Default::default()
+        };
+        let text = grid.format_highlighted(&style, &cells);
+        let first_line = text.lines().next().unwrap();
+        assert_eq!(
+            first_line,
+            format!("{HIGHLIGHT_START}1{HIGHLIGHT_END}..|{HIGHLIGHT_START}1{HIGHLIGHT_END}..|...")
+        );
+        assert_eq!(
+            text.replace(HIGHLIGHT_START, "").replace(HIGHLIGHT_END, ""),
+            grid.format_with(&style)
+        );
+        assert_eq!(
+            grid.format_highlighted(&style, &[]),
+            grid.format_with(&style)
+        );
+    }
+
+    #[test]
+    fn test_index() {
+        let mut grid: Grid = " ".repeat(81).parse().unwrap();
+        assert_eq!(grid[(3, 5)], None);
+
+        grid[(3, 5)] = Some(6);
+        assert_eq!(grid[(3, 5)], Some(6));
+        assert_eq!(grid.get(3, 5), Some(6));
+        assert_eq!(grid[(5, 3)], None);
+    }
+
+    #[test]
+    fn test_malformed_input_does_not_panic() {
+        // A valid solution with one clue changed
+        let mut broken = Grid::generate_filled(3);
+        broken[(4, 4)] = broken[(4, 4)].map(|d| (d + 1) % 9);
+
+        let grids = [
+            " ".repeat(81).parse().unwrap(),
+            // Two equal clues in a row, column and box
+            format!("11{}", " ".repeat(79)).parse().unwrap(),
+            format!("1        1{}", " ".repeat(71)).parse().unwrap(),
+            // A complete grid that breaks every rule
+            "1".repeat(81).parse().unwrap(),
+            broken,
+        ];
+        let options = [
+            SolveOptions::default(),
+            SolveOptions {
+                preprocess: true,
+                verify: true,
+                ..Default::default()
+            },
+            SolveOptions {
+                canonical: true,
+                ..Default::default()
+            },
+            SolveOptions {
+                parity: vec![(9, 100, Parity::Odd)],
+                ..Default::default()
+            },
+            SolveOptions {
+                thermometers: vec![Thermometer(vec![(0, 0), (0, 0), (20, 3)])],
+                cages: vec![Cage::new(vec![], RegionConstraint::Product(0))],
+                ..Default::default()
+            },
+            SolveOptions {
+                cages: vec![Cage::new(
+                    vec![(0, 0), (1, 0)],
+                    RegionConstraint::Sum(u32::MAX),
+                )],
+                preprocess: true,
+                ..Default::default()
+            },
+        ];
+
+        for grid in &grids {
+            for options in &options {
+                let _ = grid.solve_with(options);
+            }
+            let _ = grid.clone().solve();
+            let _ = grid.solve_raw();
+            let _ = grid.solve_excluding(grid);
+            let _ = grid.solve_biased(&Grid::generate_filled(1));
+            let _ = grid.solve_canonical();
+            let _ = grid.complete_preserving(&"9".repeat(81).parse().unwrap());
+            let _ = grid.clone().solve_backtracking();
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_index_out_of_bounds() {
+        let grid: Grid = " ".repeat(81).parse().unwrap();
+        let _ = grid[(9, 0)];
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_solve_with_tracing() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        assert!(grid.solve_with(&SolveOptions::default()).is_ok());
+        assert_eq!(grid.count_solutions(2).unwrap(), 1);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_solve_async() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        let solution = grid.clone().solve_async().await.unwrap();
+        assert_eq!(solution, grid.solve().unwrap());
+        assert_eq!(
+            Grid::from_str(&format!("11{}", " ".repeat(79)))
+                .unwrap()
+                .solve_async()
+                .await,
+            Err(SolveError::Unsatisfiable)
+        );
+    }
+
+    #[cfg(feature = "bench")]
+    #[test]
+    fn test_solve_repeated() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        assert_eq!(grid.solve_repeated(3).unwrap().len(), 3);
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert_eq!(
+            contradictory.solve_repeated(3).err(),
+            Some(SolveError::Unsatisfiable)
+        );
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_solve_with_log() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        assert!(grid.can_place(2, 0, 3).unwrap());
+        assert!(grid.solve_canonical().is_ok());
+        assert!(grid.solve().is_ok());
+    }
+}
@@ -0,0 +1,832 @@
+//! A solver that fills in cells the way a human would, used to explain how a puzzle is solved.
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+};
+
+use crate::{box_cells, box_index, rng::Rng, Grid, SolveError};
+
+/// A technique used to make progress in a grid. The variants are ordered from easiest to hardest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Technique {
+    /// The digit is the only candidate left in the cell.
+    NakedSingle,
+    /// The cell is the only place left for the digit in one of its units.
+    HiddenSingle,
+    /// Candidates were removed because of a [`NakedPair`].
+    NakedPair,
+    /// Candidates were removed because of a [`PointingPair`].
+    PointingPair,
+    /// No technique applied, so the digit was taken from a solution found by search.
+    RequiresSearch,
+}
+
+/// A row, column or box of the grid. Boxes are numbered in row-major order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Row(usize),
+    Column(usize),
+    Box(usize),
+}
+
+impl Unit {
+    /// Returns the coordinates of the nine cells of the unit.
+    pub fn cells(self) -> [(usize, usize); 9] {
+        match self {
+            Unit::Row(y) => std::array::from_fn(|x| (x, y)),
+            Unit::Column(x) => std::array::from_fn(|y| (x, y)),
+            Unit::Box(b) => box_cells(b),
+        }
+    }
+
+    /// Returns all 27 units of the grid.
+    pub fn all() -> impl Iterator<Item = Unit> {
+        (0..9)
+            .map(Unit::Row)
+            .chain((0..9).map(Unit::Column))
+            .chain((0..9).map(Unit::Box))
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unit::Row(y) => write!(f, "row {}", y + 1),
+            Unit::Column(x) => write!(f, "column {}", x + 1),
+            Unit::Box(b) => write!(f, "box {}", b + 1),
+        }
+    }
+}
+
+/// A cell filled in by the logical solver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub x: usize,
+    pub y: usize,
+    pub digit: u8,
+    pub technique: Technique,
+    /// The unit in which the cell was the only place for the digit, for hidden singles.
+    pub unit: Option<Unit>,
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (cell, digit) = (format!("R{}C{}", self.y + 1, self.x + 1), self.digit + 1);
+        match (self.technique, self.unit) {
+            (Technique::HiddenSingle, Some(unit)) => write!(
+                f,
+                "Cell {cell} must be {digit}: it's the only place for a {digit} in its {unit}"
+            ),
+            (Technique::RequiresSearch, _) => write!(
+                f,
+                "Cell {cell} is {digit}: no technique applies, so a guess was required"
+            ),
+            _ => write!(
+                f,
+                "Cell {cell} must be {digit}: it's the only candidate left in the cell"
+            ),
+        }
+    }
+}
+
+/// Two cells of a unit that have the same two candidates left. Those digits have to go in these
+/// two cells, so they can be removed from the candidates of the other cells in the unit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NakedPair {
+    pub unit: Unit,
+    pub cells: [(usize, usize); 2],
+    pub digits: [u8; 2],
+    /// The candidates `(x, y, digit)` that can be removed.
+    pub eliminations: Vec<(usize, usize, u8)>,
+}
+
+/// Cells of a box that are the only candidates for a digit in that box and all lie on the same
+/// row or column. The digit has to go on that line within the box, so it can be removed from the
+/// candidates of the other cells on the line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PointingPair {
+    pub box_idx: usize,
+    /// The row or column the cells lie on.
+    pub line: Unit,
+    pub digit: u8,
+    pub cells: Vec<(usize, usize)>,
+    /// The candidates `(x, y, digit)` that can be removed.
+    pub eliminations: Vec<(usize, usize, u8)>,
+}
+
+/// Number of puzzles [`Grid::generate_with_difficulty`] generates before giving up and returning
+/// `None`.
+pub const GENERATE_ATTEMPTS: usize = 50;
+
+/// How much each use of a technique adds to the [`Difficulty`] score.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TechniqueWeights {
+    pub naked_single: u32,
+    pub hidden_single: u32,
+    pub naked_pair: u32,
+    pub pointing_pair: u32,
+    pub requires_search: u32,
+}
+
+impl TechniqueWeights {
+    /// Returns the weight of `technique`.
+    pub fn weight(&self, technique: Technique) -> u32 {
+        match technique {
+            Technique::NakedSingle => self.naked_single,
+            Technique::HiddenSingle => self.hidden_single,
+            Technique::NakedPair => self.naked_pair,
+            Technique::PointingPair => self.pointing_pair,
+            Technique::RequiresSearch => self.requires_search,
+        }
+    }
+}
+
+impl Default for TechniqueWeights {
+    fn default() -> Self {
+        Self {
+            naked_single: 1,
+            hidden_single: 2,
+            naked_pair: 5,
+            pointing_pair: 5,
+            requires_search: 20,
+        }
+    }
+}
+
+/// The rating of a puzzle, see [`Grid::difficulty`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Difficulty {
+    /// The sum of the weights of all techniques used.
+    pub score: u32,
+    /// How often each technique was used. Techniques that were not used are left out.
+    pub usage: BTreeMap<Technique, usize>,
+}
+
+/// Finds the cells that are the only place left for a digit in one of their units. Every cell is
+/// reported once, for the first unit in which it is a hidden single.
+fn hidden_singles(grid: &Grid, candidates: &[[bool; 9]; 81]) -> Vec<Step> {
+    let mut singles: Vec<Step> = Vec::new();
+    for unit in Unit::all() {
+        for d in 0..9u8 {
+            let mut places = unit
+                .cells()
+                .into_iter()
+                .filter(|&(x, y)| candidates[y * 9 + x][d as usize]);
+            if let (Some((x, y)), None) = (places.next(), places.next()) {
+                if grid.get(x, y).is_none() && !singles.iter().any(|s| (s.x, s.y) == (x, y)) {
+                    singles.push(Step {
+                        x,
+                        y,
+                        digit: d,
+                        technique: Technique::HiddenSingle,
+                        unit: Some(unit),
+                    });
+                }
+            }
+        }
+    }
+    singles
+}
+
+/// Finds the naked pairs that allow removing at least one candidate.
+fn naked_pairs(grid: &Grid, candidates: &[[bool; 9]; 81]) -> Vec<NakedPair> {
+    let digits_of = |(x, y): (usize, usize)| {
+        (0..9u8)
+            .filter(|&d| candidates[y * 9 + x][d as usize])
+            .collect::<Vec<_>>()
+    };
+
+    let mut pairs = Vec::new();
+    for unit in Unit::all() {
+        let cells = unit.cells();
+        for (i, &a) in cells.iter().enumerate() {
+            let digits = digits_of(a);
+            if grid.get(a.0, a.1).is_some() || digits.len() != 2 {
+                continue;
+            }
+            for &b in &cells[i + 1..] {
+                if grid.get(b.0, b.1).is_some() || digits_of(b) != digits {
+                    continue;
+                }
+                let eliminations = cells
+                    .iter()
+                    .filter(|&&c| c != a && c != b && grid.get(c.0, c.1).is_none())
+                    .flat_map(|&(x, y)| digits.iter().map(move |&d| (x, y, d)))
+                    .filter(|&(x, y, d)| candidates[y * 9 + x][d as usize])
+                    .collect::<Vec<_>>();
+                if !eliminations.is_empty() {
+                    pairs.push(NakedPair {
+                        unit,
+                        cells: [a, b],
+                        digits: [digits[0], digits[1]],
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    pairs
+}
+
+/// Finds the pointing pairs (and triples) that allow removing at least one candidate.
+fn pointing_pairs(grid: &Grid, candidates: &[[bool; 9]; 81]) -> Vec<PointingPair> {
+    let mut pairs = Vec::new();
+    for box_idx in 0..9 {
+        for d in 0..9u8 {
+            let cells = Unit::Box(box_idx)
+                .cells()
+                .into_iter()
+                .filter(|&(x, y)| grid.get(x, y).is_none() && candidates[y * 9 + x][d as usize])
+                .collect::<Vec<_>>();
+            if cells.len() < 2 {
+                continue;
+            }
+
+            let line = if cells.iter().all(|&(_, y)| y == cells[0].1) {
+                Unit::Row(cells[0].1)
+            } else if cells.iter().all(|&(x, _)| x == cells[0].0) {
+                Unit::Column(cells[0].0)
+            } else {
+                continue;
+            };
+
+            let eliminations = line
+                .cells()
+                .into_iter()
+                .filter(|&(x, y)| box_index(x, y) != box_idx)
+                .filter(|&(x, y)| grid.get(x, y).is_none() && candidates[y * 9 + x][d as usize])
+                .map(|(x, y)| (x, y, d))
+                .collect::<Vec<_>>();
+            if !eliminations.is_empty() {
+                pairs.push(PointingPair {
+                    box_idx,
+                    line,
+                    digit: d,
+                    cells,
+                    eliminations,
+                });
+            }
+        }
+    }
+    pairs
+}
+
+/// Grid along with the candidates of every cell.
+pub(crate) struct LogicalSolver {
+    pub grid: Grid,
+    pub candidates: [[bool; 9]; 81],
+}
+
+impl LogicalSolver {
+    pub fn new(grid: &Grid) -> Self {
+        Self {
+            grid: grid.clone(),
+            candidates: grid.candidates(),
+        }
+    }
+
+    /// Fills in a cell and removes the digit from the candidates of its peers.
+    pub fn place(&mut self, x: usize, y: usize, d: u8) {
+        self.grid.cells[y * 9 + x] = Some(d);
+        self.candidates[y * 9 + x] = [false; 9];
+        self.candidates[y * 9 + x][d as usize] = true;
+        for unit in [Unit::Row(y), Unit::Column(x), Unit::Box(box_index(x, y))] {
+            for (px, py) in unit.cells() {
+                if (px, py) != (x, y) {
+                    self.candidates[py * 9 + px][d as usize] = false;
+                }
+            }
+        }
+    }
+
+    fn find_naked_single(&self) -> Option<Step> {
+        (0..81)
+            .filter(|&i| self.grid.cells[i].is_none())
+            .find_map(|i| {
+                let mut digits = (0..9).filter(|&d| self.candidates[i][d]);
+                match (digits.next(), digits.next()) {
+                    (Some(d), None) => Some(Step {
+                        x: i % 9,
+                        y: i / 9,
+                        digit: d as u8,
+                        technique: Technique::NakedSingle,
+                        unit: None,
+                    }),
+                    _ => None,
+                }
+            })
+    }
+
+    fn find_hidden_single(&self) -> Option<Step> {
+        hidden_singles(&self.grid, &self.candidates)
+            .into_iter()
+            .next()
+    }
+
+    /// Removes the candidates ruled out by the first naked pair or, failing that, the first
+    /// pointing pair. Returns the technique used, or `None` if neither applies.
+    fn eliminate(&mut self) -> Option<Technique> {
+        let (technique, eliminations) =
+            match naked_pairs(&self.grid, &self.candidates).into_iter().next() {
+                Some(pair) => (Technique::NakedPair, pair.eliminations),
+                None => {
+                    let pair = pointing_pairs(&self.grid, &self.candidates)
+                        .into_iter()
+                        .next()?;
+                    (Technique::PointingPair, pair.eliminations)
+                }
+            };
+        for (x, y, d) in eliminations {
+            self.candidates[y * 9 + x][d as usize] = false;
+        }
+        Some(technique)
+    }
+
+    /// Finds and applies the next logical step, or returns `None` if no technique applies.
+    pub fn step(&mut self) -> Option<Step> {
+        let step = self
+            .find_naked_single()
+            .or_else(|| self.find_hidden_single())?;
+        self.place(step.x, step.y, step.digit);
+        Some(step)
+    }
+
+    /// Applies steps until none applies. Returns whether that filled in the whole grid.
+    fn solve_singles(mut self) -> bool {
+        while self.step().is_some() {}
+        self.grid.is_complete()
+    }
+}
+
+/// Returns the digit of cell `(x, y)` in a solution found by the SAT solver.
+fn solution_digit(solution: &Grid, x: usize, y: usize) -> Result<u8, SolveError> {
+    solution.get(x, y).ok_or_else(|| {
+        SolveError::InternalInconsistency(format!("cell ({x}, {y}) is empty in the solution"))
+    })
+}
+
+impl Grid {
+    /// Solves the grid one cell at a time using human techniques and returns the steps taken.
+    /// Whenever no technique applies, the most constrained cell is filled in from a solution found
+    /// by the SAT solver and marked as [`Technique::RequiresSearch`].
+    pub fn solve_steps(&self) -> Result<Vec<Step>, SolveError> {
+        let solution = self.clone().solve()?;
+        let mut solver = LogicalSolver::new(self);
+        let mut steps = Vec::new();
+        while let Some((x, y)) = solver.grid.most_constrained_cell() {
+            let step = match solver.step() {
+                Some(step) => step,
+                None => {
+                    let digit = solution_digit(&solution, x, y)?;
+                    solver.place(x, y, digit);
+                    Step {
+                        x,
+                        y,
+                        digit,
+                        technique: Technique::RequiresSearch,
+                        unit: None,
+                    }
+                }
+            };
+            steps.push(step);
+        }
+        Ok(steps)
+    }
+
+    /// Returns the grid after each of the [steps](Grid::solve_steps) that solve it, for animating
+    /// the solve. Every snapshot has one more cell filled in than the one before and the last one
+    /// is the solution. Returns no snapshots if the grid is already filled in, and the error of
+    /// [`Grid::solve_steps`] if it has no solution or the solver fails.
+    pub fn solve_snapshots(&self) -> Result<Vec<Grid>, SolveError> {
+        let steps = self.solve_steps()?;
+        Ok(steps
+            .iter()
+            .scan(self.clone(), |grid, step| {
+                grid.cells[step.y * 9 + step.x] = Some(step.digit);
+                Some(grid.clone())
+            })
+            .collect())
+    }
+
+    /// Rates the grid by solving it with human techniques, applying the simplest one that makes
+    /// progress at every point: singles first, then naked and pointing pairs to remove
+    /// candidates, and a guess from a solution found by the SAT solver as a last resort. Every use
+    /// of a technique adds its weight to the score.
+    pub fn difficulty(&self, weights: &TechniqueWeights) -> Result<Difficulty, SolveError> {
+        let solution = self.clone().solve()?;
+        let mut solver = LogicalSolver::new(self);
+        let mut usage = BTreeMap::new();
+        while let Some((x, y)) = solver.grid.most_constrained_cell() {
+            let technique = match solver.step() {
+                Some(step) => step.technique,
+                None => match solver.eliminate() {
+                    Some(technique) => technique,
+                    None => {
+                        solver.place(x, y, solution_digit(&solution, x, y)?);
+                        Technique::RequiresSearch
+                    }
+                },
+            };
+            *usage.entry(technique).or_insert(0) += 1;
+        }
+
+        let score = usage
+            .iter()
+            .map(|(&technique, &count)| weights.weight(technique) * count as u32)
+            .sum();
+        Ok(Difficulty { score, usage })
+    }
+
+    /// Clears one clue such that the grid keeps a unique solution, picking the one that makes the
+    /// [difficulty](Grid::difficulty) with the default weights highest. Ties are broken randomly
+    /// (determined by `seed`). Returns `None` if no clue can be cleared, so calling this until it
+    /// does drives the grid towards a hard minimal puzzle.
+    pub fn make_harder(&self, seed: u64) -> Option<Grid> {
+        let mut clues = self.filled_cells().collect::<Vec<_>>();
+        Rng::new(seed).shuffle(&mut clues);
+
+        let weights = TechniqueWeights::default();
+        clues
+            .into_iter()
+            .filter_map(|(x, y, _)| {
+                let mut easier = self.clone();
+                easier.cells[y * 9 + x] = None;
+                if !easier.has_unique_solution() {
+                    return None;
+                }
+                let score = easier.difficulty(&weights).ok()?.score;
+                Some((score, easier))
+            })
+            .reduce(|best, candidate| {
+                if candidate.0 > best.0 {
+                    candidate
+                } else {
+                    best
+                }
+            })
+            .map(|(_, harder)| harder)
+    }
+
+    /// Returns the empty cells that are a backdoor of size one: given their value in the solution,
+    /// the rest of the grid follows from naked and hidden singles alone. A grid that singles
+    /// solve already has every empty cell as a backdoor, and one that needs more than a single
+    /// extra clue has none. For a grid with several solutions, the values are taken from the one
+    /// found by the SAT solver. Returns an empty list if the grid has no solution.
+    pub fn backdoors(&self) -> Vec<(usize, usize)> {
+        let Ok(solution) = self.clone().solve() else {
+            return Vec::new();
+        };
+        self.empty_cells()
+            .filter(|&(x, y)| {
+                solution.get(x, y).is_some_and(|d| {
+                    let mut solver = LogicalSolver::new(self);
+                    solver.place(x, y, d);
+                    solver.solve_singles()
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the hardest technique needed to solve the grid, in the order of [`Technique`], as
+    /// a difficulty label. A grid that is already filled in counts as needing naked singles.
+    pub fn hardest_technique(&self) -> Result<Technique, SolveError> {
+        let difficulty = self.difficulty(&TechniqueWeights::default())?;
+        Ok(difficulty
+            .usage
+            .into_keys()
+            .max()
+            .unwrap_or(Technique::NakedSingle))
+    }
+
+    /// Generates a puzzle with a unique solution (determined by `seed`) whose [hardest
+    /// technique](Grid::hardest_technique) is `target`. Every attempt reduces a random solution to
+    /// a minimal puzzle, which mostly needs more than singles, and then gives back clues of the
+    /// solution in a random order until the puzzle is no harder than `target`. The puzzle is
+    /// therefore only minimal for targets that minimal puzzles meet.
+    ///
+    /// The band is given as the hardest [`Technique`] rather than as a [`Difficulty`], which is
+    /// the score of one particular solve and not a band a puzzle can be made to fall in. Returns
+    /// `None` rather than a grid if the hardest technique of none of the [`GENERATE_ATTEMPTS`]
+    /// attempts is exactly `target`, which can happen for the harder targets.
+    pub fn generate_with_difficulty(seed: u64, target: Technique) -> Option<Grid> {
+        let mut rng = Rng::new(seed);
+        (0..GENERATE_ATTEMPTS).find_map(|_| {
+            let seed = rng.next_u64();
+            let solution = Grid::generate_filled(seed);
+            let mut puzzle = solution.reduce_to_minimal(seed);
+            let mut hardest = puzzle.hardest_technique().ok()?;
+
+            // Adding clues of the solution keeps the solution unique
+            let mut cells = puzzle.empty_cells().collect::<Vec<_>>();
+            rng.shuffle(&mut cells);
+            for (x, y) in cells {
+                if hardest <= target {
+                    break;
+                }
+                puzzle[(x, y)] = solution.get(x, y);
+                hardest = puzzle.hardest_technique().ok()?;
+            }
+            debug!(
+                "generated puzzle with {} clues needs {hardest:?}, looking for {target:?}",
+                puzzle.clue_count()
+            );
+            (hardest == target).then_some(puzzle)
+        })
+    }
+
+    /// Returns every hidden single in the grid, based on [`Grid::candidates`].
+    pub fn find_hidden_singles(&self) -> Vec<Step> {
+        hidden_singles(self, &self.candidates())
+    }
+
+    /// Returns every naked pair in the grid that removes candidates, based on
+    /// [`Grid::candidates`].
+    pub fn find_naked_pairs(&self) -> Vec<NakedPair> {
+        naked_pairs(self, &self.candidates())
+    }
+
+    /// Returns every pointing pair (or triple) in the grid that removes candidates, based on
+    /// [`Grid::candidates`].
+    pub fn find_pointing_pairs(&self) -> Vec<PointingPair> {
+        pointing_pairs(self, &self.candidates())
+    }
+
+    /// Returns a step-by-step explanation of how the grid can be solved, one line per filled in
+    /// cell. Lines note when no technique applied and a guess was required.
+    pub fn explain(&self) -> Vec<String> {
+        match self.solve_steps() {
+            Ok(steps) => steps.iter().map(Step::to_string).collect(),
+            Err(_) => vec!["The puzzle has no solution".to_string()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::is_valid_solution;
+
+    #[test]
+    fn test_solve_steps() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        let steps = grid.solve_steps().unwrap();
+        assert_eq!(steps.len(), 81 - grid.clue_count());
+        assert!(steps
+            .iter()
+            .all(|step| step.technique != Technique::RequiresSearch));
+
+        let mut solved = grid.clone();
+        for step in &steps {
+            assert_eq!(solved.get(step.x, step.y), None);
+            solved[(step.x, step.y)] = Some(step.digit);
+        }
+        assert!(is_valid_solution(&solved));
+        assert_eq!(solved, grid.solve().unwrap());
+    }
+
+    #[test]
+    fn test_solve_snapshots() {
+        let grid: Grid =
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  "
+                .parse()
+                .unwrap();
+
+        let snapshots = grid.solve_snapshots().unwrap();
+        assert_eq!(snapshots.len(), 81 - grid.clue_count());
+        assert_eq!(snapshots.last(), Some(&grid.clone().solve().unwrap()));
+        let mut previous = &grid;
+        for snapshot in &snapshots {
+            assert_eq!(snapshot.clue_count(), previous.clue_count() + 1);
+            assert_eq!(snapshot.edit_distance(previous), 1);
+            previous = snapshot;
+        }
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert_eq!(
+            contradictory.solve_snapshots(),
+            Err(SolveError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn test_find_hidden_singles() {
+        // The 1s in rows 1 and 2 and columns 1 and 2 leave (0, 0) as the only place for a 1 in
+        // the top-left box.
+        let mut grid = Grid::empty();
+        for (x, y) in [(3, 1), (6, 2), (1, 3), (2, 6)] {
+            grid[(x, y)] = Some(0);
+        }
+
+        let singles = grid.find_hidden_singles();
+        let single = singles.iter().find(|s| (s.x, s.y) == (0, 0)).unwrap();
+        assert_eq!(single.digit, 0);
+        assert_eq!(single.technique, Technique::HiddenSingle);
+        assert_eq!(grid, {
+            let mut expected = Grid::empty();
+            for (x, y) in [(3, 1), (6, 2), (1, 3), (2, 6)] {
+                expected[(x, y)] = Some(0);
+            }
+            expected
+        });
+    }
+
+    #[test]
+    fn test_find_naked_pairs() {
+        let grid: Grid = format!("  3456789{}", " ".repeat(72)).parse().unwrap();
+
+        let pairs = grid.find_naked_pairs();
+        let pair = pairs.iter().find(|p| p.unit == Unit::Box(0)).unwrap();
+        assert_eq!(pair.cells, [(0, 0), (1, 0)]);
+        assert_eq!(pair.digits, [0, 1]);
+        assert!(pair.eliminations.contains(&(0, 1, 0)));
+        assert!(pair.eliminations.contains(&(2, 2, 1)));
+        assert_eq!(pair.eliminations.len(), 12);
+    }
+
+    #[test]
+    fn test_find_pointing_pairs() {
+        let grid: Grid = format!("{}234      567{}", " ".repeat(9), " ".repeat(60))
+            .parse()
+            .unwrap();
+
+        // The 1 of the top-left box has to go in the top row.
+        let pairs = grid.find_pointing_pairs();
+        let pair = pairs.iter().find(|p| p.digit == 0).unwrap();
+        assert_eq!(pair.box_idx, 0);
+        assert_eq!(pair.line, Unit::Row(0));
+        assert_eq!(pair.cells, vec![(0, 0), (1, 0), (2, 0)]);
+        assert_eq!(
+            pair.eliminations,
+            (3..9).map(|x| (x, 0, 0)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_difficulty() {
+        // Only needs naked singles
+        let easy: Grid =
+            "  3 2 6  9  3 5  1  18 64    81 29  7       8  67 82    26 95  8  2 3  9  5 1 3  "
+                .parse()
+                .unwrap();
+        // Needs eliminations and guesses
+        let hard: Grid =
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  "
+                .parse()
+                .unwrap();
+
+        let weights = TechniqueWeights::default();
+        let easy_rating = easy.difficulty(&weights).unwrap();
+        assert_eq!(
+            easy_rating.usage,
+            BTreeMap::from([(Technique::NakedSingle, 81 - easy.clue_count())])
+        );
+        assert_eq!(easy_rating.score, (81 - easy.clue_count()) as u32);
+
+        let hard_rating = hard.difficulty(&weights).unwrap();
+        assert!(hard_rating.usage.contains_key(&Technique::RequiresSearch));
+        assert!(hard_rating.score > easy_rating.score);
+        assert_eq!(
+            hard_rating.score,
+            hard_rating
+                .usage
+                .iter()
+                .map(|(&t, &n)| weights.weight(t) * n as u32)
+                .sum::<u32>()
+        );
+
+        // Only counting guesses rates the easy puzzle as trivial
+        let guesses_only = TechniqueWeights {
+            naked_single: 0,
+            hidden_single: 0,
+            naked_pair: 0,
+            pointing_pair: 0,
+            requires_search: 1,
+        };
+        assert_eq!(easy.difficulty(&guesses_only).unwrap().score, 0);
+        assert_eq!(
+            hard.difficulty(&guesses_only).unwrap().score as usize,
+            hard_rating.usage[&Technique::RequiresSearch]
+        );
+    }
+
+    #[test]
+    fn test_make_harder() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let weights = TechniqueWeights::default();
+
+        let harder = grid.make_harder(1).unwrap();
+        assert_eq!(harder.clue_count(), grid.clue_count() - 1);
+        assert!(harder.has_unique_solution());
+        assert_eq!(harder.edit_distance(&grid), 1);
+        assert!(
+            harder.difficulty(&weights).unwrap().score >= grid.difficulty(&weights).unwrap().score
+        );
+        assert_eq!(grid.make_harder(1), Some(harder));
+
+        let minimal = grid.clone().solve().unwrap().reduce_to_minimal(3);
+        assert_eq!(minimal.make_harder(1), None);
+    }
+
+    #[test]
+    fn test_generate_with_difficulty() {
+        let easy = Grid::generate_with_difficulty(6, Technique::HiddenSingle).unwrap();
+        assert!(easy.has_unique_solution());
+        assert_eq!(easy.hardest_technique(), Ok(Technique::HiddenSingle));
+        assert_eq!(
+            Grid::generate_with_difficulty(6, Technique::HiddenSingle),
+            Some(easy)
+        );
+
+        // Minimal puzzles practically never get by with naked singles alone
+        let easiest = Grid::generate_with_difficulty(1, Technique::NakedSingle).unwrap();
+        assert!(easiest.has_unique_solution());
+        assert!(!easiest.is_complete());
+        assert_eq!(easiest.hardest_technique(), Ok(Technique::NakedSingle));
+    }
+
+    #[test]
+    fn test_backdoors() {
+        // Singles get stuck on this puzzle, but not once the second cell is given
+        let puzzle: Grid =
+            "  79   4  38 41   2         7 8    4    2   5  659 3    4 7 6     6   29    85   "
+                .parse()
+                .unwrap();
+        let backdoors = puzzle.backdoors();
+        assert_eq!(backdoors.len(), 17);
+        assert_eq!(backdoors[0], (1, 0));
+        assert!(!backdoors.contains(&(0, 0)));
+        assert!(!LogicalSolver::new(&puzzle).solve_singles());
+        let mut given = puzzle.clone();
+        given.cells[1] = puzzle.clone().solve().unwrap().get(1, 0);
+        assert!(given.hardest_technique().unwrap() <= Technique::HiddenSingle);
+
+        // The hardest puzzles need more than a single extra clue
+        let hard: Grid =
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  "
+                .parse()
+                .unwrap();
+        assert!(hard.backdoors().is_empty());
+
+        let easy: Grid =
+            "  3 2 6  9  3 5  1  18 64    81 29  7       8  67 82    26 95  8  2 3  9  5 1 3  "
+                .parse()
+                .unwrap();
+        assert_eq!(easy.backdoors().len(), easy.empty_cells().count());
+    }
+
+    #[test]
+    fn test_hardest_technique() {
+        let easy: Grid =
+            "  3 2 6  9  3 5  1  18 64    81 29  7       8  67 82    26 95  8  2 3  9  5 1 3  "
+                .parse()
+                .unwrap();
+        assert_eq!(easy.hardest_technique(), Ok(Technique::NakedSingle));
+
+        let hard: Grid =
+            "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  "
+                .parse()
+                .unwrap();
+        assert_eq!(hard.hardest_technique(), Ok(Technique::RequiresSearch));
+
+        let solved = easy.clone().solve().unwrap();
+        assert_eq!(solved.hardest_technique(), Ok(Technique::NakedSingle));
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert_eq!(
+            contradictory.hardest_technique(),
+            Err(SolveError::Unsatisfiable)
+        );
+    }
+
+    #[test]
+    fn test_explain() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        let explanation = grid.explain();
+        assert_eq!(explanation.len(), 81 - grid.clue_count());
+        assert!(explanation[0].starts_with("Cell R"));
+
+        // An empty grid can only be solved by guessing
+        let explanation = Grid::empty().explain();
+        assert!(explanation[0].ends_with("a guess was required"));
+
+        let mut solved = Grid::empty();
+        for step in Grid::empty().solve_steps().unwrap() {
+            solved[(step.x, step.y)] = Some(step.digit);
+        }
+        assert!(is_valid_solution(&solved));
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        assert_eq!(contradictory.explain(), vec!["The puzzle has no solution"]);
+    }
+}
@@ -1,210 +1,365 @@
 use std::{
-    fmt::{Display, Formatter},
-    io,
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process::ExitCode,
     str::FromStr,
     time::Instant,
 };
-use varisat::{CnfFormula, ExtendFormula, Solver, Var};
 
-type Cell = Option<u8>;
+use clap::{Parser, Subcommand, ValueEnum};
+use sudosat::{DisplayStyle, Grid, SolveError};
 
-#[derive(Clone)]
-struct Grid {
-    cells: [Cell; 81],
+/// Solves a sudoku read from stdin as a single line of 81 characters.
+#[derive(Parser)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// How to print the solution.
+    #[arg(long, value_enum, default_value_t = Format::Pretty)]
+    format: Format,
+
+    /// Solve every line of stdin, writing one solution line per puzzle as it is found.
+    #[arg(long, conflicts_with = "format")]
+    batch: bool,
+
+    /// Also check whether the solution is unique, and print a second solution if it is not.
+    #[arg(long, conflicts_with = "batch")]
+    check_unique: bool,
 }
 
-fn sudoku_formula() -> CnfFormula {
-    let mut formula = CnfFormula::new();
-
-    for y in 0..9 {
-        for x in 0..9 {
-            // Only one value per cell
-            for a in 0..9 {
-                let v_a = Var::from_index(y * 81 + x * 9 + a);
-                for b in (a + 1)..9 {
-                    let v_b = Var::from_index(y * 81 + x * 9 + b);
-                    formula.add_clause(&[v_a.negative(), v_b.negative()]);
-                }
-            }
+#[derive(Subcommand)]
+enum Command {
+    /// Time solving the puzzles in a file, one per line, and report the throughput.
+    Bench {
+        /// The file with the puzzles.
+        #[arg(long)]
+        file: PathBuf,
 
-            // Each cell must contain at least one value.
-            let select_at_least_one_clause = (0..9)
-                .into_iter()
-                .map(|v| Var::from_index(y * 81 + x * 9 + v).positive())
-                .collect::<Vec<_>>();
-            formula.add_clause(&select_at_least_one_clause);
-        }
-    }
+        /// Only solve the first this many puzzles.
+        #[arg(long)]
+        count: Option<usize>,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    /// A single line of 81 characters.
+    Line,
+    /// A human readable grid, along with the input and timing.
+    Pretty,
+    /// A standalone SVG image.
+    Svg,
+    /// A JSON array of rows.
+    Json,
+}
 
-    // For each row
-    for y in 0..9 {
-        for x in 0..9 {
-            for d in 0..9 {
-                for x2 in 0..9 {
-                    if x != x2 {
-                        formula.add_clause(&[
-                            Var::from_index(y * 81 + x * 9 + d).negative(),
-                            Var::from_index(y * 81 + x2 * 9 + d).negative(),
-                        ])
-                    }
-                }
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    if let Some(Command::Bench { file, count }) = &args.command {
+        return match bench(file, *count) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
             }
-        }
+        };
     }
 
-    // For each column
-    for x in 0..9 {
-        for y in 0..9 {
-            for d in 0..9 {
-                for y2 in 0..9 {
-                    if y != y2 {
-                        formula.add_clause(&[
-                            Var::from_index(y * 81 + x * 9 + d).negative(),
-                            Var::from_index(y2 * 81 + x * 9 + d).negative(),
-                        ])
-                    }
-                }
+    if args.batch {
+        return match batch() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
             }
+        };
+    }
+
+    if args.format == Format::Pretty {
+        println!("Input your sudoku: ");
+    }
+    let mut line = String::new();
+    io::stdin().read_line(&mut line).unwrap();
+
+    let grid = Grid::from_str(line.trim_end_matches(['\r', '\n'])).unwrap();
+    if args.format == Format::Pretty {
+        println!("input:\n{}", format_input(&grid));
+    }
+
+    let start_solve = Instant::now();
+    let result = grid.clone().solve();
+    let end_solve = Instant::now();
+    let solution = match result {
+        Ok(solution) => solution,
+        Err(SolveError::Unsatisfiable) => {
+            println!(
+                "No solution! ({} ms)",
+                (end_solve - start_solve).as_micros() as f64 / 1_000.0
+            );
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
         }
+    };
+
+    if args.format == Format::Pretty {
+        println!(
+            "solution ({} ms):",
+            (end_solve - start_solve).as_micros() as f64 / 1_000.0
+        );
     }
+    print_grid(args.format, &solution);
 
-    // For each block
-    for block_idx in 0..9 {
-        for i in 0..9 {
-            for d in 0..9 {
-                for i2 in 0..9 {
-                    if i != i2 {
-                        let x1 = i % 3;
-                        let y1 = i / 3;
-                        let x2 = i2 % 3;
-                        let y2 = i2 / 3;
-                        let bx = block_idx % 3;
-                        let by = block_idx / 3;
-                        formula.add_clause(&[
-                            Var::from_index((by * 3 + y1) * 81 + (bx * 3 + x1) * 9 + d).negative(),
-                            Var::from_index((by * 3 + y2) * 81 + (bx * 3 + x2) * 9 + d).negative(),
-                        ])
-                    }
-                }
+    if args.check_unique {
+        match second_solution(grid.solve_excluding(&solution)) {
+            Ok(None) => println!("The solution is unique"),
+            Ok(Some(other)) => {
+                println!("The solution is not unique, another solution is:");
+                print_grid(args.format, &other);
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::FAILURE;
             }
         }
     }
+    ExitCode::SUCCESS
+}
 
-    formula
+/// Turns the result of looking for another solution with [`Grid::solve_excluding`] into that
+/// solution, if there is one. Only [`SolveError::Unsatisfiable`] means the solution is unique;
+/// other errors are passed on rather than mistaken for uniqueness.
+fn second_solution(result: Result<Grid, SolveError>) -> Result<Option<Grid>, SolveError> {
+    match result {
+        Ok(other) => Ok(Some(other)),
+        Err(SolveError::Unsatisfiable) => Ok(None),
+        Err(e) => Err(e),
+    }
 }
 
-impl Grid {
-    pub fn get(&self, x: usize, y: usize) -> Cell {
-        self.cells[y * 9 + x]
+/// Solves the puzzles on stdin with [`sudosat::solve_stream`]. With the `progress` feature, a
+/// progress bar is drawn on stderr if that is a terminal.
+fn batch() -> io::Result<()> {
+    #[cfg(feature = "progress")]
+    if io::stderr().is_terminal() {
+        let mut bar = progress::ProgressBar::new(progress::stdin_len());
+        let result =
+            sudosat::solve_stream_with_progress(io::stdin().lock(), io::stdout().lock(), |p| {
+                bar.update(p)
+            });
+        bar.finish();
+        return result;
     }
+    sudosat::solve_stream(io::stdin().lock(), io::stdout().lock())
+}
 
-    pub fn solve(mut self) -> Result<Grid, ()> {
-        let mut solver = Solver::new();
+/// Solves the first `count` puzzles of `file` with [`sudosat::solve_stream`] and prints how long
+/// that took.
+fn bench(file: &Path, count: Option<usize>) -> anyhow::Result<()> {
+    let contents = fs::read_to_string(file)?;
+    let puzzles = contents
+        .lines()
+        .filter(|line| !line.trim_end_matches('\r').is_empty())
+        .take(count.unwrap_or(usize::MAX))
+        .collect::<Vec<_>>();
+    let input = puzzles.join("\n");
 
-        // Construct formula
-        solver.add_formula(&sudoku_formula());
+    let mut output = Vec::new();
+    let start = Instant::now();
+    sudosat::solve_stream(input.as_bytes(), &mut output)?;
+    let elapsed = start.elapsed();
 
-        // Add filled in values
-        for y in 0..9 {
-            for x in 0..9 {
-                if let Some(d) = self.get(x, y) {
-                    let v = Var::from_index(y * 81 + x * 9 + d as usize);
-                    solver.add_clause(&[v.positive()]);
-                }
-            }
-        }
+    let failed = output
+        .split(|&b| b == b'\n')
+        .filter(|line| line.starts_with(b"error: "))
+        .count();
+    let n = puzzles.len();
+    println!("puzzles: {n} ({failed} without a solution or malformed)");
+    println!("total: {:.3} ms", elapsed.as_secs_f64() * 1_000.0);
+    if n > 0 {
+        println!(
+            "per puzzle: {:.3} ms",
+            elapsed.as_secs_f64() * 1_000.0 / n as f64
+        );
+        println!("puzzles/sec: {:.1}", n as f64 / elapsed.as_secs_f64());
+    }
+    Ok(())
+}
 
-        // Solve the damn thing
-        if !solver.solve().unwrap() {
-            return Err(());
+/// Formats the input grid for the pretty output. In a terminal the clues that conflict with
+/// each other are highlighted.
+fn format_input(grid: &Grid) -> String {
+    if !io::stdout().is_terminal() {
+        return grid.to_string();
+    }
+    let conflicting = grid
+        .conflicts()
+        .into_iter()
+        .flat_map(|(a, b)| [a, b])
+        .collect::<Vec<_>>();
+    grid.format_highlighted(&DisplayStyle::default(), &conflicting)
+}
+
+/// Prints a grid in the given format.
+fn print_grid(format: Format, grid: &Grid) {
+    match format {
+        Format::Line => println!("{}", grid.to_line()),
+        Format::Pretty => println!("{grid}"),
+        Format::Svg => print!("{}", grid.to_svg()),
+        Format::Json => println!("{}", grid.to_json()),
+    }
+}
+
+/// A progress bar for `--batch`, behind the `progress` feature. Drawn by hand rather than with
+/// `indicatif`, which is not available to the build; see `FOLLOW_UPS.md`.
+#[cfg(feature = "progress")]
+mod progress {
+    use std::{
+        io::{self, Write},
+        time::{Duration, Instant},
+    };
+
+    use sudosat::StreamProgress;
+
+    /// Width of the bar in characters.
+    const WIDTH: usize = 30;
+    /// The bar is redrawn at most this often.
+    const REDRAW: Duration = Duration::from_millis(100);
+
+    /// Draws the number of puzzles solved and the rate on a single line of stderr. If the length
+    /// of the input is known, also the fraction done and the estimated time left.
+    pub struct ProgressBar {
+        total_bytes: Option<u64>,
+        start: Instant,
+        last_draw: Option<Instant>,
+        last: StreamProgress,
+    }
+
+    impl ProgressBar {
+        pub fn new(total_bytes: Option<u64>) -> Self {
+            Self {
+                total_bytes: total_bytes.filter(|&total| total > 0),
+                start: Instant::now(),
+                last_draw: None,
+                last: StreamProgress::default(),
+            }
         }
 
-        // Get the values from the model
-        let model = solver.model().unwrap();
-        for var in model {
-            if var.is_positive() {
-                let digit = (var.index() % 9) as u8;
-                let x = (var.index() % 81) / 9;
-                let y = var.index() / 81;
-                match std::mem::replace(&mut self.cells[y * 9 + x], Some(digit)) {
-                    Some(prev) if prev != digit => {
-                        unreachable!("decided something else!")
-                    }
-                    _ => {}
-                }
+        pub fn update(&mut self, progress: &StreamProgress) {
+            self.last = *progress;
+            if self.last_draw.is_none_or(|last| last.elapsed() >= REDRAW) {
+                self.draw();
             }
         }
 
-        Ok(self)
-    }
-}
+        /// Draws the final state and ends the line.
+        pub fn finish(&mut self) {
+            self.draw();
+            eprintln!();
+        }
 
-impl FromStr for Grid {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self {
-            cells: s
-                .chars()
-                .map(|c| match c {
-                    '1'..='9' => c
-                        .to_digit(10)
-                        .map(|d| d as u8 - 1)
-                        .ok_or_else(|| format!("invalid digit '{c}'"))
-                        .map(Some),
-                    ' ' => Ok(None),
-                    _ => Err(format!("invalid character '{c}'")),
-                })
-                .collect::<Result<Vec<Cell>, _>>()?
-                .try_into()
-                .map_err(|e: Vec<Cell>| format!("failed to convert {:?} ({})", &e, e.len()))?,
-        })
+        fn draw(&mut self) {
+            self.last_draw = Some(Instant::now());
+            let line = status_line(&self.last, self.total_bytes, self.start.elapsed());
+            // Clear the rest of the line, in case the previous one was longer
+            eprint!("\r{line}\x1b[K");
+            let _ = io::stderr().flush();
+        }
     }
-}
 
-impl Display for Grid {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        for y in 0..9 {
-            for x in 0..9 {
-                match self.get(x, y) {
-                    None => write!(f, "  ")?,
-                    Some(d) => write!(f, "{} ", d + 1)?,
-                }
-                if x < 8 && (x + 1) % 3 == 0 {
-                    write!(f, "| ")?;
-                }
-            }
-            writeln!(f)?;
-            if y < 8 && (y + 1) % 3 == 0 {
-                writeln!(f, "---------------------")?;
+    /// Formats the state of the bar after `elapsed`: the bar, percentage and estimated time left
+    /// if the length of the input is known, followed by the number of puzzles and the rate.
+    fn status_line(
+        progress: &StreamProgress,
+        total_bytes: Option<u64>,
+        elapsed: Duration,
+    ) -> String {
+        let elapsed = elapsed.as_secs_f64();
+        let puzzles = progress.puzzles;
+        let rate = if elapsed > 0.0 {
+            puzzles as f64 / elapsed
+        } else {
+            0.0
+        };
+        let mut line = String::new();
+        if let Some(total) = total_bytes {
+            let fraction = (progress.bytes as f64 / total as f64).min(1.0);
+            let filled = (fraction * WIDTH as f64) as usize;
+            line.push_str(&format!(
+                "[{}{}] {:3.0}% ",
+                "#".repeat(filled),
+                "-".repeat(WIDTH - filled),
+                fraction * 100.0
+            ));
+            if fraction > 0.0 {
+                let eta = elapsed * (1.0 - fraction) / fraction;
+                line.push_str(&format!("ETA {eta:.0}s "));
             }
         }
-        Ok(())
+        line.push_str(&format!("{puzzles} puzzles, {rate:.1}/s"));
+        line
     }
-}
 
-fn main() {
-    println!("Input your sudoku: ");
-    let mut line = String::new();
-    io::stdin().read_line(&mut line).unwrap();
+    /// Returns the length of stdin if it is redirected from a file.
+    #[cfg(unix)]
+    pub fn stdin_len() -> Option<u64> {
+        use std::os::fd::AsFd;
 
-    let grid = Grid::from_str(line.trim_end_matches(&['\r', '\n'])).unwrap();
-    println!("input:\n{grid}");
+        let fd = io::stdin().as_fd().try_clone_to_owned().ok()?;
+        let metadata = std::fs::File::from(fd).metadata().ok()?;
+        metadata.is_file().then_some(metadata.len())
+    }
 
-    let start_solve = Instant::now();
-    let result = grid.solve();
-    let end_solve = Instant::now();
-    match result {
-        Ok(solution) => {
-            println!(
-                "solution ({} ms):\n{solution}",
-                (end_solve - start_solve).as_micros() as f64 / 1_000.0
+    #[cfg(not(unix))]
+    pub fn stdin_len() -> Option<u64> {
+        None
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn test_status_line() {
+            let progress = StreamProgress {
+                puzzles: 50,
+                bytes: 250,
+            };
+            let elapsed = Duration::from_secs(10);
+            assert_eq!(
+                status_line(&progress, Some(1000), elapsed),
+                format!(
+                    "[{}{}]  25% ETA 30s 50 puzzles, 5.0/s",
+                    "#".repeat(7),
+                    "-".repeat(23)
+                )
+            );
+
+            // Without the length of the input only the count and rate are known
+            assert_eq!(status_line(&progress, None, elapsed), "50 puzzles, 5.0/s");
+
+            // Nothing read yet, so no estimate, and no rate before any time has passed
+            let start = StreamProgress::default();
+            assert_eq!(
+                status_line(&start, Some(1000), Duration::ZERO),
+                format!("[{}]   0% 0 puzzles, 0.0/s", "-".repeat(30))
+            );
+
+            // Input that grew while reading does not overflow the bar
+            let over = StreamProgress {
+                puzzles: 3,
+                bytes: 2000,
+            };
+            assert_eq!(
+                status_line(&over, Some(1000), Duration::from_secs(2)),
+                format!("[{}] 100% ETA 0s 3 puzzles, 1.5/s", "#".repeat(30))
             );
         }
-        Err(_) => println!(
-            "No solution! ({} ms)",
-            (end_solve - start_solve).as_micros() as f64 / 1_000.0
-        ),
     }
 }
 
@@ -213,18 +368,11 @@ mod test {
     use super::*;
 
     #[test]
-    fn test_solve() {
-        let grid: Grid =
-            // "86   7           4 9 58 2    7   8 663   2  5 1  9  3  7   53  3    6     9    1 "
-        // "1    7 9  3  2   8  96  5    53  9   1  8   26    4   3      1  41     7  7   3  "
-        // "1                                                                               2"
-        // "8          36      7  9 2   5   7       457     1   3   1    68  85   1  9    4  "
-        "     6    59     82    8    45        3        6  3 54   325  6                  "
-                .parse()
-                .unwrap();
-
-        let grid = grid.solve().unwrap();
-
-        println!("{grid}");
+    fn test_second_solution() {
+        let grid = Grid::empty();
+        assert_eq!(second_solution(Ok(grid.clone())), Ok(Some(grid)));
+        assert_eq!(second_solution(Err(SolveError::Unsatisfiable)), Ok(None));
+        let failure = SolveError::InternalInconsistency("solver failed".to_string());
+        assert_eq!(second_solution(Err(failure.clone())), Err(failure));
     }
 }
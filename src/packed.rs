@@ -0,0 +1,126 @@
+//! Compact binary and text encodings of grids, for storage and sharing.
+use crate::Grid;
+
+/// Number of bytes of the packed encoding: one nibble per cell.
+const PACKED_LEN: usize = 41;
+
+/// The URL-safe base64 alphabet (RFC 4648, section 5).
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encodes `bytes` as URL-safe base64 without padding.
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() * 4).div_ceil(3));
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (i, &b)| group | (b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            encoded.push(BASE64_ALPHABET[(group >> (18 - 6 * i)) as usize & 0x3f] as char);
+        }
+    }
+    encoded
+}
+
+/// Decodes URL-safe base64 without padding.
+fn decode_base64(s: &str) -> Result<Vec<u8>, String> {
+    let sextets = s
+        .chars()
+        .map(|c| {
+            BASE64_ALPHABET
+                .iter()
+                .position(|&b| b as char == c)
+                .map(|i| i as u32)
+                .ok_or_else(|| format!("invalid base64 character '{c}'"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut bytes = Vec::with_capacity(sextets.len() * 3 / 4);
+    for chunk in sextets.chunks(4) {
+        if chunk.len() == 1 {
+            return Err("truncated base64 input".to_string());
+        }
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (i, &s)| group | s << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            bytes.push((group >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+impl Grid {
+    /// Packs the grid into 41 bytes, two cells per byte in row-major order with the first cell in
+    /// the high nibble. A nibble holds the digit (1-9) or 0 for an empty cell.
+    pub fn to_bytes(&self) -> [u8; 41] {
+        let nibble = |i: usize| self.cells.get(i).copied().flatten().map_or(0, |d| d + 1);
+        std::array::from_fn(|i| (nibble(2 * i) << 4) | nibble(2 * i + 1))
+    }
+
+    /// Unpacks a grid packed by [`Grid::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Grid, String> {
+        if bytes.len() != PACKED_LEN {
+            return Err(format!("expected {PACKED_LEN} bytes, got {}", bytes.len()));
+        }
+        if bytes[PACKED_LEN - 1] & 0x0f != 0 {
+            return Err("trailing nibble is not zero".to_string());
+        }
+
+        let mut grid = Grid::empty();
+        for (i, cell) in grid.cells.iter_mut().enumerate() {
+            let nibble = (bytes[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+            *cell = match nibble {
+                0 => None,
+                1..=9 => Some(nibble - 1),
+                _ => return Err(format!("invalid cell value {nibble} at index {i}")),
+            };
+        }
+        Ok(grid)
+    }
+
+    /// Encodes the grid as 55 characters of URL-safe base64 (without padding) of
+    /// [`Grid::to_bytes`], short enough to share in a link.
+    pub fn to_base64(&self) -> String {
+        encode_base64(&self.to_bytes())
+    }
+
+    /// Decodes a grid encoded by [`Grid::to_base64`].
+    pub fn from_base64(s: &str) -> Result<Grid, String> {
+        Grid::from_bytes(&decode_base64(s)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(encode_base64(b"sudoku"), "c3Vkb2t1");
+        assert_eq!(encode_base64(&[0xfb, 0xff]), "-_8");
+        assert_eq!(decode_base64("-_8"), Ok(vec![0xfb, 0xff]));
+        assert!(decode_base64("+").is_err());
+
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let bytes = grid.to_bytes();
+        assert_eq!(bytes[0], 0x53);
+        assert_eq!(Grid::from_bytes(&bytes), Ok(grid.clone()));
+
+        let encoded = grid.to_base64();
+        assert_eq!(encoded.len(), 55);
+        assert!(encoded
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(Grid::from_base64(&encoded), Ok(grid));
+
+        let solution = Grid::generate_filled(7);
+        assert_eq!(Grid::from_base64(&solution.to_base64()), Ok(solution));
+        assert!(Grid::from_base64(&encoded[..54]).is_err());
+    }
+}
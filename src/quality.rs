@@ -0,0 +1,77 @@
+//! A summary of the properties a published puzzle is expected to have.
+use crate::{Grid, PatternSymmetry};
+
+/// The result of [`Grid::quality`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PuzzleQuality {
+    /// The puzzle has exactly one solution.
+    pub unique: bool,
+    /// The puzzle has a unique solution that is lost when clearing any clue.
+    pub minimal: bool,
+    /// The number of clues.
+    pub clues: usize,
+    /// The symmetries of the positions of the clues, see [`Grid::pattern_symmetries`].
+    pub symmetries: Vec<PatternSymmetry>,
+}
+
+impl PuzzleQuality {
+    /// Returns whether the puzzle is unique, minimal and has a symmetric clue pattern, as is
+    /// customary for published puzzles.
+    pub fn is_proper(&self) -> bool {
+        self.unique && self.minimal && !self.symmetries.is_empty()
+    }
+}
+
+impl Grid {
+    /// Checks the properties of the grid as a puzzle in one go. A property the solver fails to
+    /// check is reported as missing.
+    pub fn quality(&self) -> PuzzleQuality {
+        let unique = self.has_unique_solution();
+        PuzzleQuality {
+            unique,
+            minimal: unique && self.is_minimal() == Ok(true),
+            clues: self.clue_count(),
+            symmetries: self.pattern_symmetries(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_quality() {
+        let proper: Grid =
+            "4 6  25  7      8  283       7 4   5 4     3 5   2 1       691  9      7  59  8 6"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            proper.quality(),
+            PuzzleQuality {
+                unique: true,
+                minimal: true,
+                clues: 26,
+                symmetries: vec![PatternSymmetry::Rotational180],
+            }
+        );
+        assert!(proper.quality().is_proper());
+
+        // Unique and symmetric, but some clues can be removed
+        let redundant: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let quality = redundant.quality();
+        assert!(quality.unique && !quality.minimal);
+        assert!(!quality.is_proper());
+
+        // Removing a single clue breaks the symmetry and the uniqueness
+        let mut flawed = proper.clone();
+        flawed[(0, 0)] = None;
+        let quality = flawed.quality();
+        assert!(!quality.unique && !quality.minimal);
+        assert!(quality.symmetries.is_empty());
+        assert_eq!(quality.clues, 25);
+    }
+}
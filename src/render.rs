@@ -0,0 +1,390 @@
+//! Renderers that turn a [`Grid`] into formats other than plain text, and the way back from a
+//! solver's answer to the exported formula.
+use std::{fmt::Write, io};
+
+use varisat::dimacs::{write_dimacs, write_dimacs_clauses, write_dimacs_header, DimacsHeader};
+
+use crate::{cell_for, Grid, SolveError, SolveOptions, Var, NUM_VARS};
+
+/// Size of a single cell in SVG user units.
+const SVG_CELL_SIZE: usize = 40;
+
+/// Space around the grid so the outer border is not clipped.
+const SVG_MARGIN: usize = 2;
+
+/// Default look of the table from [`Grid::to_html`], scoped to its `sudoku` class.
+const HTML_STYLE: &str = "\
+table.sudoku { border-collapse: collapse; border: 3px solid black; }
+table.sudoku td { width: 2em; height: 2em; border: 1px solid black; text-align: center; font-family: sans-serif; font-size: 1.5em; }
+table.sudoku td.box-left { border-left: 3px solid black; }
+table.sudoku td.box-top { border-top: 3px solid black; }
+";
+
+impl Grid {
+    /// Renders the grid as a standalone SVG image with thicker lines around the boxes.
+    pub fn to_svg(&self) -> String {
+        self.to_svg_with_marks(false)
+    }
+
+    /// Renders the grid like [`Grid::to_svg`]. If `marks` is set, every empty cell also shows its
+    /// [`Grid::candidates`] as pencil marks: small grey digits laid out like a keypad, 1 in the
+    /// top-left corner and 9 in the bottom-right one. The marks have the class `mark`.
+    pub fn to_svg_with_marks(&self, marks: bool) -> String {
+        let size = SVG_CELL_SIZE * 9 + 2 * SVG_MARGIN;
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {size} {size}">"#
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<rect width="{size}" height="{size}" fill="white"/>"#
+        )
+        .unwrap();
+
+        // Grid lines, the box boundaries are thicker
+        for i in 0..=9 {
+            let p = SVG_MARGIN + i * SVG_CELL_SIZE;
+            let (start, end) = (SVG_MARGIN, SVG_MARGIN + 9 * SVG_CELL_SIZE);
+            let width = if i % 3 == 0 { 3 } else { 1 };
+            writeln!(
+                svg,
+                r#"<line x1="{p}" y1="{start}" x2="{p}" y2="{end}" stroke="black" stroke-width="{width}"/>"#
+            )
+            .unwrap();
+            writeln!(
+                svg,
+                r#"<line x1="{start}" y1="{p}" x2="{end}" y2="{p}" stroke="black" stroke-width="{width}"/>"#
+            )
+            .unwrap();
+        }
+
+        // Digits, centered in their cells
+        for (x, y, d) in self.filled_cells() {
+            let cx = SVG_MARGIN + x * SVG_CELL_SIZE + SVG_CELL_SIZE / 2;
+            let cy = SVG_MARGIN + y * SVG_CELL_SIZE + SVG_CELL_SIZE / 2;
+            writeln!(
+                svg,
+                r#"<text x="{cx}" y="{cy}" font-family="sans-serif" font-size="28" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                d + 1
+            )
+            .unwrap();
+        }
+
+        if marks {
+            let candidates = self.candidates();
+            let step = SVG_CELL_SIZE / 3;
+            for (x, y) in self.empty_cells() {
+                for d in (0..9).filter(|&d| candidates[y * 9 + x][d]) {
+                    let cx = SVG_MARGIN + x * SVG_CELL_SIZE + (d % 3) * step + step / 2 + 1;
+                    let cy = SVG_MARGIN + y * SVG_CELL_SIZE + (d / 3) * step + step / 2 + 1;
+                    writeln!(
+                        svg,
+                        r#"<text class="mark" x="{cx}" y="{cy}" font-family="sans-serif" font-size="10" fill="grey" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                        d + 1
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Renders the grid as an HTML `<table>` with class `sudoku`, preceded by a `<style>` element
+    /// that draws thicker lines around the boxes. Cells on the left or top edge of a box get the
+    /// classes `box-left` and `box-top`, and every cell is either `filled` or `empty`, so the look
+    /// can be changed with CSS of the caller's own.
+    pub fn to_html(&self) -> String {
+        let mut html = format!("<style>\n{HTML_STYLE}</style>\n<table class=\"sudoku\">\n");
+        for y in 0..9 {
+            html.push_str("<tr>");
+            for x in 0..9 {
+                let mut classes = vec![if self.get(x, y).is_some() {
+                    "filled"
+                } else {
+                    "empty"
+                }];
+                if x % 3 == 0 && x > 0 {
+                    classes.push("box-left");
+                }
+                if y % 3 == 0 && y > 0 {
+                    classes.push("box-top");
+                }
+                let content = self
+                    .get(x, y)
+                    .map(|d| (d + 1).to_string())
+                    .unwrap_or_default();
+                write!(html, r#"<td class="{}">{content}</td>"#, classes.join(" ")).unwrap();
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+        html
+    }
+
+    /// Returns the formula for this grid (the sudoku rules and the filled in cells) in DIMACS CNF
+    /// format. Variable `y * 81 + x * 9 + d + 1` is true iff cell `(x, y)` contains digit `d`.
+    pub fn to_dimacs(&self) -> String {
+        self.to_dimacs_with(&SolveOptions::default())
+    }
+
+    /// Like [`Grid::to_dimacs`], but with the constraints and [`Encoding`](crate::Encoding) of
+    /// `options`. Constraints other than the cells may add auxiliary variables after the cell
+    /// variables.
+    pub fn to_dimacs_with(&self, options: &SolveOptions) -> String {
+        let mut dimacs = Vec::new();
+        write_dimacs(&mut dimacs, &self.formula(options)).unwrap();
+        String::from_utf8(dimacs).unwrap()
+    }
+
+    /// Like [`Grid::to_dimacs`], but with a `c p show` comment after the header that lists the 729
+    /// cell variables. Projected model counters then count solutions over the cells only, even if
+    /// the formula contains auxiliary variables.
+    pub fn to_dimacs_projected(&self) -> String {
+        let formula = self.formula(&SolveOptions::default());
+        let mut dimacs = Vec::new();
+        write_dimacs_header(
+            &mut dimacs,
+            DimacsHeader {
+                var_count: formula.var_count(),
+                clause_count: formula.len(),
+            },
+        )
+        .unwrap();
+        let mut dimacs = String::from_utf8(dimacs).unwrap();
+
+        dimacs.push_str("c p show");
+        for v in 1..=NUM_VARS {
+            write!(dimacs, " {v}").unwrap();
+        }
+        dimacs.push_str(" 0\n");
+
+        let mut clauses = Vec::new();
+        write_dimacs_clauses(&mut clauses, formula.iter()).unwrap();
+        dimacs.push_str(std::str::from_utf8(&clauses).unwrap());
+        dimacs
+    }
+
+    /// Decodes the model an external SAT solver found for the formula of [`Grid::to_dimacs`]. The
+    /// input holds the literals of the assignment, optionally on `v` lines as in the output of
+    /// the SAT competition format, whose `s` and `c` lines are skipped. A terminating `0` is
+    /// optional. Cells without a true variable stay empty, and variables beyond the cells are
+    /// ignored.
+    pub fn from_dimacs_model(model: &str) -> Result<Grid, String> {
+        let mut grid = Grid::empty();
+        for line in model.lines() {
+            let line = line.trim();
+            if line.starts_with('c') || line.starts_with('s') {
+                continue;
+            }
+            for literal in line.strip_prefix('v').unwrap_or(line).split_whitespace() {
+                let value = literal
+                    .parse::<isize>()
+                    .map_err(|_| format!("invalid literal '{literal}'"))?;
+                if value <= 0 || value as usize > NUM_VARS {
+                    continue;
+                }
+                let (x, y, d) = cell_for(Var::from_dimacs(value));
+                match grid.get(x, y) {
+                    Some(prev) if prev as usize != d => {
+                        return Err(format!(
+                            "cell ({x}, {y}) is both {} and {}",
+                            prev + 1,
+                            d + 1
+                        ))
+                    }
+                    _ => grid[(x, y)] = Some(d as u8),
+                }
+            }
+        }
+        Ok(grid)
+    }
+
+    /// Solves the formula of [`Grid::to_dimacs`] and writes the answer to `writer` like a SAT
+    /// solver would: an `s SATISFIABLE` line followed by the model on a single `v` line ending in
+    /// `0`, or just `s UNSATISFIABLE`. Returns whether the grid has a solution. Failures of the
+    /// solver itself are reported as I/O errors, after nothing has been written.
+    pub fn solve_to_dimacs(&self, mut writer: impl io::Write) -> io::Result<bool> {
+        let model = match self.solve_raw() {
+            Ok(model) => model,
+            Err(SolveError::Unsatisfiable) => {
+                writeln!(writer, "s UNSATISFIABLE")?;
+                return Ok(false);
+            }
+            Err(e) => return Err(io::Error::other(e)),
+        };
+
+        let mut line = String::from("v");
+        for lit in model {
+            write!(line, " {}", lit.to_dimacs()).unwrap();
+        }
+        writeln!(writer, "s SATISFIABLE")?;
+        writeln!(writer, "{line} 0")?;
+        Ok(true)
+    }
+
+    /// Renders the grid as a JSON array of nine rows, each an array of nine digits (1-9) or
+    /// `null` for empty cells.
+    pub fn to_json(&self) -> String {
+        let rows = (0..9)
+            .map(|y| {
+                let cells = (0..9)
+                    .map(|x| match self.get(x, y) {
+                        Some(d) => (d + 1).to_string(),
+                        None => "null".to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                format!("[{}]", cells.join(","))
+            })
+            .collect::<Vec<_>>();
+        format!("[{}]", rows.join(","))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_svg() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        let svg = grid.to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<text").count(), grid.clue_count());
+        assert_eq!(svg.matches("<line").count(), 20);
+        assert_eq!(grid.to_svg_with_marks(false), svg);
+
+        let marked = grid.to_svg_with_marks(true);
+        let candidates = grid.candidates();
+        let expected = grid
+            .empty_cells()
+            .map(|(x, y)| candidates[y * 9 + x].iter().filter(|&&c| c).count())
+            .sum::<usize>();
+        assert_eq!(marked.matches(r#"<text class="mark""#).count(), expected);
+        assert_eq!(
+            marked.matches("<text").count(),
+            grid.clue_count() + expected
+        );
+        // The top-left cell of the third row can hold 1 and 2
+        assert!(marked.contains(r#"<text class="mark" x="9" y="89""#));
+    }
+
+    #[test]
+    fn test_to_html() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+
+        let html = grid.to_html();
+        assert!(html.contains(r#"<table class="sudoku">"#));
+        assert!(html.trim_end().ends_with("</table>"));
+        assert_eq!(html.matches("<tr>").count(), 9);
+        assert_eq!(html.matches("<td").count(), 81);
+        assert_eq!(html.matches("filled").count(), grid.clue_count());
+        // The style mentions each box class once
+        assert_eq!(html.matches("box-left").count() - 1, 2 * 9);
+        assert_eq!(html.matches("box-top").count() - 1, 2 * 9);
+        assert!(html.contains(
+            r#"<td class="filled">5</td><td class="filled">3</td><td class="empty"></td>"#
+        ));
+    }
+
+    #[test]
+    fn test_to_dimacs() {
+        let grid: Grid = format!("53{}", " ".repeat(79)).parse().unwrap();
+
+        let formula = varisat::dimacs::DimacsParser::parse(grid.to_dimacs().as_bytes()).unwrap();
+        assert_eq!(formula.var_count(), 729);
+        assert!(formula.iter().any(|clause| clause.len() == 1));
+
+        let projected = grid.to_dimacs_projected();
+        let show = projected
+            .lines()
+            .find_map(|line| line.strip_prefix("c p show "))
+            .unwrap();
+        let vars = show
+            .split_whitespace()
+            .map(|v| v.parse::<usize>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(vars.last(), Some(&0));
+        assert_eq!(vars[..vars.len() - 1], (1..=729).collect::<Vec<_>>());
+
+        let formula = varisat::dimacs::DimacsParser::parse(projected.as_bytes()).unwrap();
+        assert_eq!(formula.len(), grid.to_dimacs().lines().count() - 1);
+    }
+
+    #[test]
+    fn test_solve_to_dimacs() {
+        let puzzle: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let mut output = Vec::new();
+        assert!(puzzle.solve_to_dimacs(&mut output).unwrap());
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "s SATISFIABLE");
+        assert!(lines[1].starts_with("v ") && lines[1].ends_with(" 0"));
+        assert_eq!(
+            Grid::from_dimacs_model(&output),
+            Ok(puzzle.solve().unwrap())
+        );
+
+        let contradictory: Grid = format!("11{}", " ".repeat(79)).parse().unwrap();
+        let mut output = Vec::new();
+        assert!(!contradictory.solve_to_dimacs(&mut output).unwrap());
+        assert_eq!(output, b"s UNSATISFIABLE\n");
+    }
+
+    #[test]
+    fn test_from_dimacs_model() {
+        let solution = Grid::generate_filled(2);
+        let literals = (1..=NUM_VARS as isize)
+            .map(|v| {
+                let (x, y, d) = cell_for(Var::from_dimacs(v));
+                if solution.get(x, y) == Some(d as u8) {
+                    v.to_string()
+                } else {
+                    (-v).to_string()
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let model = format!(
+            "s SATISFIABLE\nv {}\nv {} 0\n",
+            literals[..400].join(" "),
+            literals[400..].join(" ")
+        );
+        assert_eq!(Grid::from_dimacs_model(&model), Ok(solution.clone()));
+        assert_eq!(Grid::from_dimacs_model(&literals.join(" ")), Ok(solution));
+
+        // Variable 1 is a 1 at (0, 0), variable 11 a 2 at (1, 0)
+        let partial = Grid::from_dimacs_model("1 -2 11 800 0").unwrap();
+        assert_eq!(partial.to_line().trim_end(), "12");
+        assert_eq!(
+            Grid::from_dimacs_model("1 2"),
+            Err("cell (0, 0) is both 1 and 2".to_string())
+        );
+        assert!(Grid::from_dimacs_model("v 1 x").is_err());
+    }
+
+    #[test]
+    fn test_to_json() {
+        let grid: Grid = format!("53{}9", " ".repeat(78)).parse().unwrap();
+
+        let json = grid.to_json();
+        assert!(json.starts_with("[[5,3,null,null,"));
+        assert!(json.ends_with(",null,9]]"));
+        assert_eq!(json.matches("null").count(), 78);
+    }
+}
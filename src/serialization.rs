@@ -0,0 +1,151 @@
+//! Serde support, behind the `serde` feature.
+use std::str::FromStr;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Grid;
+
+/// The shapes a grid is accepted in.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Repr {
+    /// The line format of [`Grid::from_str`].
+    Line(String),
+    /// Nine rows of nine digits 1-9, with `null` or 0 for empty cells, as produced by
+    /// [`Grid::to_json`].
+    Rows(Vec<Vec<Option<u8>>>),
+}
+
+/// Serializes the grid in the line format of [`Grid::to_line`].
+impl Serialize for Grid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_line())
+    }
+}
+
+/// Deserializes either a string in the line format of [`Grid::from_str`] or nine rows of nine
+/// digits 1-9, in which `null` or 0 is an empty cell, so both the output of [`Serialize`] and
+/// that of [`Grid::to_json`] are accepted.
+impl<'de> Deserialize<'de> for Grid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Repr::deserialize(deserializer)? {
+            Repr::Line(line) => Grid::from_str(&line),
+            Repr::Rows(rows) => {
+                if rows.len() != 9 {
+                    return Err(de::Error::custom(format!(
+                        "grid has {} rows instead of 9",
+                        rows.len()
+                    )));
+                }
+                let mut array = [[0; 9]; 9];
+                for (y, row) in rows.iter().enumerate() {
+                    if row.len() != 9 {
+                        return Err(de::Error::custom(format!(
+                            "row {y} has {} cells instead of 9",
+                            row.len()
+                        )));
+                    }
+                    for (x, cell) in row.iter().enumerate() {
+                        array[y][x] = cell.unwrap_or(0);
+                    }
+                }
+                Grid::from_array(&array)
+            }
+        }
+        .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde::de::{
+        value::{Error, SeqDeserializer},
+        IntoDeserializer, Visitor,
+    };
+
+    use super::*;
+
+    /// Just enough of a JSON value to feed both shapes to the deserializer.
+    enum Value {
+        Null,
+        Number(u8),
+        String(String),
+        Array(Vec<Value>),
+    }
+
+    impl<'de> IntoDeserializer<'de, Error> for Value {
+        type Deserializer = Self;
+
+        fn into_deserializer(self) -> Self {
+            self
+        }
+    }
+
+    impl<'de> Deserializer<'de> for Value {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self {
+                Value::Null => visitor.visit_none(),
+                Value::Number(n) => visitor.visit_u8(n),
+                Value::String(s) => visitor.visit_string(s),
+                Value::Array(values) => visitor.visit_seq(SeqDeserializer::new(values.into_iter())),
+            }
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+            option unit unit_struct newtype_struct seq tuple tuple_struct map struct enum
+            identifier ignored_any
+        }
+    }
+
+    fn rows(grid: &Grid) -> Value {
+        Value::Array(
+            grid.to_array()
+                .iter()
+                .map(|row| {
+                    Value::Array(
+                        row.iter()
+                            .map(|&d| {
+                                if d == 0 {
+                                    Value::Null
+                                } else {
+                                    Value::Number(d)
+                                }
+                            })
+                            .collect(),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let line =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79";
+        let grid = Grid::deserialize(Value::String(line.to_string())).unwrap();
+        assert_eq!(grid, Grid::from_str(line).unwrap());
+        assert_eq!(Grid::deserialize(rows(&grid)).unwrap(), grid);
+
+        // 0 is an empty cell as well
+        let mut zeros = rows(&grid);
+        if let Value::Array(rows) = &mut zeros {
+            rows[0] = Value::Array(
+                [5, 3, 0, 0, 7, 0, 0, 0, 0]
+                    .into_iter()
+                    .map(Value::Number)
+                    .collect(),
+            );
+        }
+        assert_eq!(Grid::deserialize(zeros).unwrap(), grid);
+
+        let mut short = rows(&grid);
+        if let Value::Array(rows) = &mut short {
+            rows.pop();
+        }
+        assert!(Grid::deserialize(short).is_err());
+        assert!(Grid::deserialize(Value::Number(5)).is_err());
+    }
+}
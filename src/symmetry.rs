@@ -0,0 +1,726 @@
+//! The symmetries of sudoku: transformations that map every valid grid to another valid grid.
+use crate::{rng::Rng, Cell, Grid};
+
+/// The 64-bit FNV-1a hash of `bytes`.
+fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// All 1296 orderings of the rows (or columns) that keep the bands (or stacks) intact: the bands
+/// are permuted and so are the rows within each band. Entry `i` is the row that ends up at `i`.
+fn line_permutations() -> Vec<[usize; 9]> {
+    const PERMUTATIONS: [[usize; 3]; 6] = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+
+    let mut result = Vec::with_capacity(1296);
+    for bands in PERMUTATIONS {
+        for first in PERMUTATIONS {
+            for second in PERMUTATIONS {
+                for third in PERMUTATIONS {
+                    let within = [first, second, third];
+                    result.push(std::array::from_fn(|i| {
+                        bands[i / 3] * 3 + within[i / 3][i % 3]
+                    }));
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Returns whether `order` contains every value below its length once.
+fn is_permutation(order: &[usize]) -> bool {
+    (0..order.len()).all(|i| order.contains(&i))
+}
+
+/// Returns the permutation that undoes `order`.
+fn invert(order: [usize; 9]) -> [usize; 9] {
+    let mut inverse = [0; 9];
+    for (i, &o) in order.iter().enumerate() {
+        inverse[o] = i;
+    }
+    inverse
+}
+
+/// The row (or column) order that moves band (or stack) `order[i]` to `i`, or reorders the
+/// lines within band `band` the same way if `band` is given.
+fn line_order(order: [usize; 3], band: Option<usize>) -> Option<[usize; 9]> {
+    if !is_permutation(&order) || band.is_some_and(|band| band >= 3) {
+        return None;
+    }
+    Some(std::array::from_fn(|i| match band {
+        None => order[i / 3] * 3 + i % 3,
+        Some(band) if i / 3 == band => band * 3 + order[i % 3],
+        Some(_) => i,
+    }))
+}
+
+/// A combination of symmetries: optionally transposing, then reordering the rows and columns and
+/// relabeling the digits. See [`Grid::equivalence_transform`].
+///
+/// The builders below each produce a single symmetry, and [`Transform::then`] combines them, so
+/// every rotation, reflection and shuffle of bands, stacks, rows, columns and digits can be
+/// expressed as a `Transform`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transform {
+    transpose: bool,
+    /// `rows[i]` is the row that ends up at `i`, see [`line_permutations`].
+    rows: [usize; 9],
+    columns: [usize; 9],
+    /// `digits[d]` is the digit that replaces `d`.
+    digits: [u8; 9],
+}
+
+impl Transform {
+    /// Picks a random transform, uniformly among all of them.
+    fn random(rng: &mut Rng) -> Self {
+        let permutations = line_permutations();
+        let mut digits = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+        rng.shuffle(&mut digits);
+        Self {
+            transpose: rng.below(2) == 1,
+            rows: permutations[rng.below(permutations.len())],
+            columns: permutations[rng.below(permutations.len())],
+            digits,
+        }
+    }
+
+    /// The transform that leaves every grid as it is.
+    pub fn identity() -> Self {
+        Self {
+            transpose: false,
+            rows: std::array::from_fn(|i| i),
+            columns: std::array::from_fn(|i| i),
+            digits: std::array::from_fn(|d| d as u8),
+        }
+    }
+
+    /// Mirrors the grid along the main diagonal.
+    pub fn transpose() -> Self {
+        Self {
+            transpose: true,
+            ..Self::identity()
+        }
+    }
+
+    /// Replaces every (0-based) digit `d` by `digits[d]`. Returns `None` if `digits` is not a
+    /// permutation of 0-8.
+    pub fn relabel(digits: [u8; 9]) -> Option<Self> {
+        is_permutation(&digits.map(usize::from)).then_some(Self {
+            digits,
+            ..Self::identity()
+        })
+    }
+
+    /// Moves band `order[i]` to position `i`. Returns `None` if `order` is not a permutation of
+    /// 0-2.
+    pub fn permute_bands(order: [usize; 3]) -> Option<Self> {
+        Some(Self {
+            rows: line_order(order, None)?,
+            ..Self::identity()
+        })
+    }
+
+    /// Moves stack `order[i]` to position `i`.
+    pub fn permute_stacks(order: [usize; 3]) -> Option<Self> {
+        Some(Self {
+            columns: line_order(order, None)?,
+            ..Self::identity()
+        })
+    }
+
+    /// Moves row `order[i]` of band `band` to position `i` within the band.
+    pub fn permute_rows_in_band(band: usize, order: [usize; 3]) -> Option<Self> {
+        Some(Self {
+            rows: line_order(order, Some(band))?,
+            ..Self::identity()
+        })
+    }
+
+    /// Moves column `order[i]` of stack `stack` to position `i` within the stack.
+    pub fn permute_columns_in_stack(stack: usize, order: [usize; 3]) -> Option<Self> {
+        Some(Self {
+            columns: line_order(order, Some(stack))?,
+            ..Self::identity()
+        })
+    }
+
+    /// Mirrors the grid top to bottom.
+    pub fn mirror_horizontal() -> Self {
+        Self {
+            rows: std::array::from_fn(|i| 8 - i),
+            ..Self::identity()
+        }
+    }
+
+    /// Mirrors the grid left to right.
+    pub fn mirror_vertical() -> Self {
+        Self {
+            columns: std::array::from_fn(|i| 8 - i),
+            ..Self::identity()
+        }
+    }
+
+    /// Rotates the grid by 90 degrees clockwise.
+    pub fn rotate_clockwise() -> Self {
+        Self::transpose().then(&Self::mirror_vertical())
+    }
+
+    /// Returns the transform that applies this one and then `next`.
+    pub fn then(&self, next: &Transform) -> Transform {
+        // Transposing after reordering is the same as transposing first and swapping the roles
+        // of the row and column orders.
+        let (rows, columns) = if next.transpose {
+            (self.columns, self.rows)
+        } else {
+            (self.rows, self.columns)
+        };
+        Transform {
+            transpose: self.transpose != next.transpose,
+            rows: next.rows.map(|r| rows[r]),
+            columns: next.columns.map(|c| columns[c]),
+            digits: self.digits.map(|d| next.digits[d as usize]),
+        }
+    }
+
+    /// Returns the transform that undoes this one.
+    pub fn inverse(&self) -> Transform {
+        let (rows, columns) = if self.transpose {
+            (self.columns, self.rows)
+        } else {
+            (self.rows, self.columns)
+        };
+        let digits = invert(self.digits.map(usize::from));
+        Transform {
+            transpose: self.transpose,
+            rows: invert(rows),
+            columns: invert(columns),
+            digits: digits.map(|d| d as u8),
+        }
+    }
+
+    /// Whether the grid is mirrored along the main diagonal before the rows and columns are
+    /// reordered.
+    pub fn transposes(&self) -> bool {
+        self.transpose
+    }
+
+    /// Entry `i` is the row that ends up at row `i`. The bands stay intact.
+    pub fn rows(&self) -> [usize; 9] {
+        self.rows
+    }
+
+    /// Entry `i` is the column that ends up at column `i`. The stacks stay intact.
+    pub fn columns(&self) -> [usize; 9] {
+        self.columns
+    }
+
+    /// Entry `d` is the (0-based) digit that replaces digit `d`.
+    pub fn digits(&self) -> [u8; 9] {
+        self.digits
+    }
+
+    /// Applies the transform to any grid. Valid solutions map to valid solutions.
+    pub fn apply(&self, grid: &Grid) -> Grid {
+        let source = if self.transpose {
+            grid.transposed()
+        } else {
+            grid.clone()
+        };
+        let mut result = Grid::empty();
+        for y in 0..9 {
+            for x in 0..9 {
+                result[(x, y)] = source
+                    .get(self.columns[x], self.rows[y])
+                    .map(|d| self.digits[d as usize]);
+            }
+        }
+        result
+    }
+}
+
+/// A geometric symmetry of the positions of the clues.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PatternSymmetry {
+    /// Rotating the grid by 180 degrees.
+    Rotational180,
+    /// Rotating the grid by 90 degrees.
+    Rotational90,
+    /// Mirroring the top half onto the bottom half.
+    Horizontal,
+    /// Mirroring the left half onto the right half.
+    Vertical,
+    /// Mirroring along the main diagonal, from the top left to the bottom right.
+    Diagonal,
+    /// Mirroring along the anti-diagonal, from the top right to the bottom left.
+    AntiDiagonal,
+}
+
+impl PatternSymmetry {
+    const ALL: [PatternSymmetry; 6] = [
+        PatternSymmetry::Rotational180,
+        PatternSymmetry::Rotational90,
+        PatternSymmetry::Horizontal,
+        PatternSymmetry::Vertical,
+        PatternSymmetry::Diagonal,
+        PatternSymmetry::AntiDiagonal,
+    ];
+
+    /// Returns the position that `(x, y)` is mapped to.
+    fn map(self, x: usize, y: usize) -> (usize, usize) {
+        match self {
+            PatternSymmetry::Rotational180 => (8 - x, 8 - y),
+            PatternSymmetry::Rotational90 => (8 - y, x),
+            PatternSymmetry::Horizontal => (x, 8 - y),
+            PatternSymmetry::Vertical => (8 - x, y),
+            PatternSymmetry::Diagonal => (y, x),
+            PatternSymmetry::AntiDiagonal => (8 - y, 8 - x),
+        }
+    }
+}
+
+/// A partial digit relabeling built up while matching columns, which can be rolled back when a
+/// column turns out not to match.
+struct Relabeling {
+    forward: [Option<u8>; 9],
+    backward: [Option<u8>; 9],
+}
+
+impl Relabeling {
+    /// Tries to additionally map every `from[i]` to `to[i]`. Returns the digits that were newly
+    /// mapped, or `None` (leaving the relabeling untouched) if that contradicts the mapping so far.
+    fn extend(&mut self, from: &[Cell; 9], to: &[Cell; 9]) -> Option<Vec<u8>> {
+        let mut added = Vec::new();
+        for (&a, &b) in from.iter().zip(to) {
+            let consistent = match (a, b) {
+                (None, None) => true,
+                (Some(a), Some(b)) => match (self.forward[a as usize], self.backward[b as usize]) {
+                    (None, None) => {
+                        self.forward[a as usize] = Some(b);
+                        self.backward[b as usize] = Some(a);
+                        added.push(a);
+                        true
+                    }
+                    (Some(fa), Some(_)) => fa == b,
+                    _ => false,
+                },
+                _ => false,
+            };
+            if !consistent {
+                self.undo(&added);
+                return None;
+            }
+        }
+        Some(added)
+    }
+
+    /// Removes the mappings of the digits returned by [`Relabeling::extend`].
+    fn undo(&mut self, added: &[u8]) {
+        for &a in added {
+            if let Some(b) = self.forward[a as usize].take() {
+                self.backward[b as usize] = None;
+            }
+        }
+    }
+}
+
+impl Grid {
+    /// Returns the grid mirrored along the main diagonal.
+    fn transposed(&self) -> Grid {
+        let mut grid = Grid::empty();
+        for (x, y, d) in self.filled_cells() {
+            grid[(y, x)] = Some(d);
+        }
+        grid
+    }
+
+    /// Returns the symmetries that map the filled in cells onto filled in cells, in the order of
+    /// [`PatternSymmetry`]. Only the positions of the clues are compared, not their digits.
+    pub fn pattern_symmetries(&self) -> Vec<PatternSymmetry> {
+        PatternSymmetry::ALL
+            .into_iter()
+            .filter(|&symmetry| {
+                self.filled_cells().all(|(x, y, _)| {
+                    let (x, y) = symmetry.map(x, y);
+                    self.get(x, y).is_some()
+                })
+            })
+            .collect()
+    }
+
+    /// Returns a random grid (determined by `seed`) that is equivalent to this one, see
+    /// [`Grid::equivalent_to`]. A solved grid stays solved and a puzzle keeps its number of
+    /// solutions.
+    pub fn random_equivalent(&self, seed: u64) -> Grid {
+        Transform::random(&mut Rng::new(seed)).apply(self)
+    }
+
+    /// Returns a hash that is the same for equivalent grids (see [`Grid::equivalent_to`]) and
+    /// usually differs for grids that are not. Comparing fingerprints first avoids most of the
+    /// expensive equivalence checks when deduplicating a collection of puzzles. The hash is
+    /// computed with FNV-1a over a fixed encoding, so it is the same on every platform and Rust
+    /// release and can be stored.
+    pub fn fingerprint(&self) -> u64 {
+        // For every band the sorted clue counts of its rows and boxes, with the bands sorted, so
+        // the signature does not depend on the order of the rows, columns, bands and stacks.
+        let signature = |grid: &Grid| {
+            let box_counts = grid.box_clue_counts();
+            let mut bands = (0..3)
+                .map(|band| {
+                    let mut rows = (band * 3..band * 3 + 3)
+                        .map(|y| grid.row(y).iter().flatten().count())
+                        .collect::<Vec<_>>();
+                    let mut boxes = box_counts[band * 3..band * 3 + 3].to_vec();
+                    rows.sort_unstable();
+                    boxes.sort_unstable();
+                    (rows, boxes)
+                })
+                .collect::<Vec<_>>();
+            bands.sort_unstable();
+            bands
+        };
+
+        // Transposing swaps the bands and the stacks
+        let mut lines = [signature(self), signature(&self.transposed())];
+        lines.sort_unstable();
+
+        // Relabeling the digits only reorders their frequencies
+        let mut frequencies = [0usize; 9];
+        for (_, _, d) in self.filled_cells() {
+            frequencies[d as usize] += 1;
+        }
+        frequencies.sort_unstable();
+
+        // Every count fits in a byte
+        let counts = lines
+            .iter()
+            .flatten()
+            .flat_map(|(rows, boxes)| rows.iter().chain(boxes))
+            .chain(&frequencies)
+            .map(|&count| count as u8);
+        fnv1a(counts)
+    }
+
+    /// Applies the transform to the grid, see [`Transform::apply`].
+    pub fn apply(&self, transform: &Transform) -> Grid {
+        transform.apply(self)
+    }
+
+    /// Returns whether `other` can be obtained from this grid by relabeling the digits, permuting
+    /// the rows within a band, the columns within a stack, the bands and the stacks, and
+    /// transposing. Puzzles related this way are essentially the same puzzle.
+    pub fn equivalent_to(&self, other: &Grid) -> bool {
+        self.equivalence_transform(other).is_some()
+    }
+
+    /// Returns a transform that turns this grid into `other`, or `None` if they are not
+    /// equivalent (see [`Grid::equivalent_to`]). If several transforms do, the one returned is
+    /// unspecified; digits that do not appear in the grid are mapped to the digits left over.
+    pub fn equivalence_transform(&self, other: &Grid) -> Option<Transform> {
+        if self.clue_count() != other.clue_count() {
+            return None;
+        }
+
+        let row_counts = |grid: &Grid, y: usize| grid.row(y).iter().flatten().count();
+        let permutations = line_permutations();
+        for transpose in [false, true] {
+            let source = if transpose {
+                self.transposed()
+            } else {
+                self.clone()
+            };
+            for rows in &permutations {
+                // Cheap check before matching columns: the mapped rows must have as many clues.
+                if !(0..9).all(|y| row_counts(&source, rows[y]) == row_counts(other, y)) {
+                    continue;
+                }
+                let mut columns = [None; 9];
+                let mut relabeling = Relabeling {
+                    forward: [None; 9],
+                    backward: [None; 9],
+                };
+                if source.match_columns(other, rows, &mut columns, &mut relabeling, 0) {
+                    // Digits without clues can go anywhere that is still free
+                    let mut free = (0..9).filter(|&b| relabeling.backward[b as usize].is_none());
+                    let digits = relabeling
+                        .forward
+                        .map(|b| b.or_else(|| free.next()).unwrap_or_default());
+                    return Some(Transform {
+                        transpose,
+                        rows: *rows,
+                        columns: columns.map(Option::unwrap_or_default),
+                        digits,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    /// Tries to pick, for every column from `x` onwards, a column of `self` that matches column `x`
+    /// of `other` when the rows are ordered by `rows`. `columns[i]` holds the columns picked so
+    /// far, which keep the stacks intact.
+    fn match_columns(
+        &self,
+        other: &Grid,
+        rows: &[usize; 9],
+        columns: &mut [Option<usize>; 9],
+        relabeling: &mut Relabeling,
+        x: usize,
+    ) -> bool {
+        if x == 9 {
+            return true;
+        }
+
+        let target = other.col(x);
+        let candidates = match x % 3 {
+            // The first column of a stack picks a stack that has not been used yet
+            0 => (0..9)
+                .filter(|&c| (0..x).step_by(3).all(|s| columns[s].unwrap() / 3 != c / 3))
+                .collect::<Vec<_>>(),
+            // The other columns stay within the stack picked by the first one
+            _ => {
+                let stack = columns[x - x % 3].unwrap() / 3;
+                (stack * 3..stack * 3 + 3)
+                    .filter(|&c| !columns[x - x % 3..x].contains(&Some(c)))
+                    .collect()
+            }
+        };
+
+        for c in candidates {
+            let source = std::array::from_fn(|y| self.get(c, rows[y]));
+            if let Some(added) = relabeling.extend(&source, &target) {
+                columns[x] = Some(c);
+                if self.match_columns(other, rows, columns, relabeling, x + 1) {
+                    return true;
+                }
+                columns[x] = None;
+                relabeling.undo(&added);
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_equivalent_to() {
+        let grid: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        assert!(grid.equivalent_to(&grid));
+
+        // Relabel the digits, swap the first two bands, swap two columns in the last stack and
+        // transpose
+        let mut copy = Grid::empty();
+        for (x, y, d) in grid.filled_cells() {
+            let y = if y < 6 { (y + 3) % 6 } else { y };
+            let x = match x {
+                7 => 8,
+                8 => 7,
+                x => x,
+            };
+            copy[(y, x)] = Some((d + 4) % 9);
+        }
+        assert_ne!(copy, grid);
+        assert_eq!(
+            grid.equivalence_transform(&copy).unwrap().apply(&grid),
+            copy
+        );
+        assert!(grid.equivalent_to(&copy));
+        assert!(copy.equivalent_to(&grid));
+
+        // Same clue count, but a different puzzle
+        let unrelated: Grid =
+            "  3 2 6  9  3 5  1  18 64    81 29  7       8  67 82    26 95  8  2 3  9  5 1 3  "
+                .parse()
+                .unwrap();
+        let unrelated = unrelated.keep_only(
+            &unrelated
+                .filled_cells()
+                .map(|(x, y, _)| (x, y))
+                .take(grid.clue_count())
+                .collect::<Vec<_>>(),
+        );
+        assert!(!grid.equivalent_to(&unrelated));
+    }
+
+    #[test]
+    fn test_equivalence_transform() {
+        let solution = Grid::generate_filled(4);
+        let transform = Transform::random(&mut Rng::new(5));
+        let shuffled = transform.apply(&solution);
+
+        // A random solution has no symmetries of its own, so the transform is the only one
+        let recovered = solution.equivalence_transform(&shuffled).unwrap();
+        assert_eq!(recovered, transform);
+
+        // It applies to any grid, such as a puzzle with that solution
+        let puzzle = solution.reduce_to_minimal(1);
+        assert_eq!(recovered.apply(&puzzle).solve(), Ok(shuffled.clone()));
+
+        assert_eq!(
+            solution.equivalence_transform(&Grid::generate_filled(6)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_transform_builders() {
+        let grid = Grid::empty().with_clue(0, 0, 0).with_clue(4, 1, 1);
+
+        let rotated = grid.apply(&Transform::rotate_clockwise());
+        assert_eq!(rotated, Grid::empty().with_clue(8, 0, 0).with_clue(7, 4, 1));
+        let mirrored = grid.apply(&Transform::mirror_horizontal());
+        assert_eq!(
+            mirrored,
+            Grid::empty().with_clue(0, 8, 0).with_clue(4, 7, 1)
+        );
+        let mirrored = grid.apply(&Transform::mirror_vertical());
+        assert_eq!(
+            mirrored,
+            Grid::empty().with_clue(8, 0, 0).with_clue(4, 1, 1)
+        );
+
+        let bands = Transform::permute_bands([2, 0, 1]).unwrap();
+        assert_eq!(
+            grid.apply(&bands),
+            Grid::empty().with_clue(0, 3, 0).with_clue(4, 4, 1)
+        );
+        let rows = Transform::permute_rows_in_band(0, [1, 2, 0]).unwrap();
+        assert_eq!(
+            grid.apply(&rows),
+            Grid::empty().with_clue(0, 2, 0).with_clue(4, 0, 1)
+        );
+        let columns = Transform::permute_columns_in_stack(1, [2, 1, 0]).unwrap();
+        assert_eq!(
+            grid.apply(&columns),
+            Grid::empty().with_clue(0, 0, 0).with_clue(4, 1, 1)
+        );
+        let stacks = Transform::permute_stacks([1, 2, 0]).unwrap();
+        assert_eq!(
+            grid.apply(&stacks),
+            Grid::empty().with_clue(6, 0, 0).with_clue(1, 1, 1)
+        );
+        let relabel = Transform::relabel([1, 0, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        assert_eq!(
+            grid.apply(&relabel),
+            Grid::empty().with_clue(0, 0, 1).with_clue(4, 1, 0)
+        );
+
+        assert_eq!(Transform::permute_bands([0, 0, 1]), None);
+        assert_eq!(Transform::permute_rows_in_band(3, [0, 1, 2]), None);
+        assert_eq!(Transform::relabel([0; 9]), None);
+
+        // Four rotations take the grid back to where it started
+        let rotation = Transform::rotate_clockwise();
+        let full_turn = rotation.then(&rotation).then(&rotation).then(&rotation);
+        assert_eq!(full_turn, Transform::identity());
+    }
+
+    #[test]
+    fn test_transform_inverse() {
+        let solution = Grid::generate_filled(8);
+        let mut rng = Rng::new(2);
+        for _ in 0..20 {
+            let first = Transform::random(&mut rng);
+            let second = Transform::random(&mut rng);
+            let composed = first.then(&second);
+            assert_eq!(
+                solution.apply(&composed),
+                solution.apply(&first).apply(&second)
+            );
+            assert_eq!(first.then(&first.inverse()), Transform::identity());
+            assert_eq!(first.inverse().then(&first), Transform::identity());
+            assert_eq!(
+                solution.apply(&composed).apply(&composed.inverse()),
+                solution
+            );
+        }
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let puzzle: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        for seed in 0..10 {
+            assert_eq!(
+                puzzle.random_equivalent(seed).fingerprint(),
+                puzzle.fingerprint()
+            );
+        }
+        assert_eq!(puzzle.transposed().fingerprint(), puzzle.fingerprint());
+
+        let mut moved = puzzle.clone();
+        moved[(0, 0)] = None;
+        moved[(2, 0)] = Some(4);
+        assert_ne!(moved.fingerprint(), puzzle.fingerprint());
+
+        // Stored fingerprints must stay valid, so the value is pinned
+        assert_eq!(fnv1a(*b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(puzzle.fingerprint(), 0xc55b_2411_8040_63fd);
+    }
+
+    #[test]
+    fn test_pattern_symmetries() {
+        let puzzle: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            puzzle.pattern_symmetries(),
+            [PatternSymmetry::Rotational180]
+        );
+        assert_eq!(
+            Grid::empty().pattern_symmetries(),
+            PatternSymmetry::ALL.to_vec()
+        );
+
+        // The main diagonal is symmetric in every way but the 90 degree rotation
+        let mut diagonal = Grid::empty();
+        for i in 0..9 {
+            diagonal[(i, i)] = Some(i as u8);
+        }
+        assert_eq!(
+            diagonal.pattern_symmetries(),
+            [
+                PatternSymmetry::Rotational180,
+                PatternSymmetry::Diagonal,
+                PatternSymmetry::AntiDiagonal
+            ]
+        );
+    }
+
+    #[test]
+    fn test_random_equivalent() {
+        let solution = Grid::generate_filled(3);
+        let shuffled = solution.random_equivalent(11);
+        assert_ne!(shuffled, solution);
+        assert!(crate::is_valid_solution(&shuffled));
+        assert!(solution.equivalent_to(&shuffled));
+        assert_eq!(solution.random_equivalent(11), shuffled);
+
+        let puzzle: Grid =
+            "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79"
+                .parse()
+                .unwrap();
+        let shuffled = puzzle.random_equivalent(5);
+        assert_eq!(shuffled.clue_count(), puzzle.clue_count());
+        assert!(shuffled.has_unique_solution());
+        assert!(shuffled.equivalent_to(&puzzle));
+    }
+}
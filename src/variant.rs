@@ -0,0 +1,189 @@
+//! Reading the constraints of variant puzzles from text, so they can be kept in files.
+use crate::{
+    Cage, CloneRegion, EdgeClue, EdgeRelation, ExtraRegion, Parity, RegionConstraint, SolveOptions,
+    Thermometer,
+};
+
+/// Parses a cell written as `x,y`.
+fn parse_cell(word: &str) -> Result<(usize, usize), String> {
+    let parsed = word
+        .split_once(',')
+        .and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)));
+    parsed.ok_or_else(|| format!("invalid cell '{word}', expected x,y"))
+}
+
+fn parse_cells<'a>(words: impl Iterator<Item = &'a str>) -> Result<Vec<(usize, usize)>, String> {
+    words.map(parse_cell).collect()
+}
+
+/// Parses the number that follows a keyword such as `sum`.
+fn parse_number(word: Option<&str>, what: &str) -> Result<u32, String> {
+    let word = word.ok_or_else(|| format!("missing {what}"))?;
+    word.parse().map_err(|_| format!("invalid {what} '{word}'"))
+}
+
+impl SolveOptions {
+    /// Parses the constraints of a variant puzzle, one per line. Every line starts with a keyword
+    /// followed by its arguments, separated by whitespace. Cells are written as `x,y`, the
+    /// (0-based) column and row:
+    ///
+    /// - `region <cells>`: nine cells that contain every digit once, see
+    ///   [`SolveOptions::extra_regions`].
+    /// - `disjoint-groups`: sets [`SolveOptions::disjoint_groups`].
+    /// - `thermometer <cells>`: digits increase from the bulb in the first cell.
+    /// - `cage sum <n> <cells>`, `cage product <n> <cells>` and `cage unique <cells>`: a
+    ///   [`Cage`] with the [`RegionConstraint`] of the same name.
+    /// - `killer <n> <cells>`: a killer cage, the `sum` and `unique` cages over the same cells.
+    /// - `clone <cells> / <cells>`: the two lists of cells hold the same digits.
+    /// - `even <cells>` and `odd <cells>`: the parity of the digits in the cells.
+    /// - `edge sum <n> <cell> <cell>`, `edge diff <n> <cell> <cell>` and
+    ///   `edge ratio <n> <cell> <cell>`: an [`EdgeClue`] with the [`EdgeRelation`] of the same
+    ///   name.
+    ///
+    /// Blank lines and lines starting with `#` are ignored. The other options keep their default.
+    /// Errors name the line they occur on, and the constraints are checked as in
+    /// [`Grid::solve_with`](crate::Grid::solve_with).
+    pub fn from_variant(s: &str) -> Result<SolveOptions, String> {
+        let mut options = SolveOptions::default();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            options
+                .parse_variant_line(line)
+                .map_err(|e| format!("line {}: {e}", i + 1))?;
+        }
+        options.validate().map_err(|e| e.to_string())?;
+        Ok(options)
+    }
+
+    /// Adds the constraint on a single line of the format of [`SolveOptions::from_variant`].
+    fn parse_variant_line(&mut self, line: &str) -> Result<(), String> {
+        let mut words = line.split_whitespace();
+        match words.next().unwrap_or_default() {
+            "region" => self
+                .extra_regions
+                .push(ExtraRegion::new(parse_cells(words)?)),
+            "disjoint-groups" => self.disjoint_groups = true,
+            "thermometer" => self.thermometers.push(Thermometer(parse_cells(words)?)),
+            "cage" => {
+                let constraint = match words.next() {
+                    Some("sum") => RegionConstraint::Sum(parse_number(words.next(), "sum")?),
+                    Some("product") => {
+                        RegionConstraint::Product(parse_number(words.next(), "product")?)
+                    }
+                    Some("unique") => RegionConstraint::Unique,
+                    Some(other) => return Err(format!("unknown cage constraint '{other}'")),
+                    None => return Err("missing cage constraint".to_string()),
+                };
+                self.cages.push(Cage::new(parse_cells(words)?, constraint));
+            }
+            "killer" => {
+                let sum = parse_number(words.next(), "sum")?;
+                let cells = parse_cells(words)?;
+                self.cages
+                    .push(Cage::new(cells.clone(), RegionConstraint::Sum(sum)));
+                self.cages.push(Cage::new(cells, RegionConstraint::Unique));
+            }
+            "clone" => {
+                let words = words.collect::<Vec<_>>();
+                let Some(split) = words.iter().position(|&w| w == "/") else {
+                    return Err("clone needs two lists of cells separated by '/'".to_string());
+                };
+                self.clones.push(CloneRegion(
+                    parse_cells(words[..split].iter().copied())?,
+                    parse_cells(words[split + 1..].iter().copied())?,
+                ));
+            }
+            "edge" => {
+                let relation = match words.next() {
+                    Some("sum") => EdgeRelation::Sum(parse_number(words.next(), "sum")?),
+                    Some("diff") => EdgeRelation::Diff(parse_number(words.next(), "difference")?),
+                    Some("ratio") => EdgeRelation::Ratio(parse_number(words.next(), "ratio")?),
+                    Some(other) => return Err(format!("unknown edge relation '{other}'")),
+                    None => return Err("missing edge relation".to_string()),
+                };
+                let cells = parse_cells(words)?;
+                let [a, b] = cells[..] else {
+                    return Err(format!("edge has {} cells instead of 2", cells.len()));
+                };
+                self.edge_clues.push(EdgeClue::new(a, b, relation));
+            }
+            keyword @ ("even" | "odd") => {
+                let parity = if keyword == "even" {
+                    Parity::Even
+                } else {
+                    Parity::Odd
+                };
+                for (x, y) in parse_cells(words)? {
+                    self.parity.push((x, y, parity));
+                }
+            }
+            keyword => return Err(format!("unknown constraint '{keyword}'")),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_variant() {
+        let options = SolveOptions::from_variant(
+            "# A killer cage and a clone\n\
+             \n\
+             killer 3 0,0 1,0\n\
+             cage product 12 2,0 3,0\n\
+             clone 0,0 1,0 / 4,4 5,4\n\
+             even 8,8\n\
+             edge ratio 2 4,0 5,0\n\
+             disjoint-groups\n",
+        )
+        .unwrap();
+        assert_eq!(
+            options.cages,
+            vec![
+                Cage::new(vec![(0, 0), (1, 0)], RegionConstraint::Sum(3)),
+                Cage::new(vec![(0, 0), (1, 0)], RegionConstraint::Unique),
+                Cage::new(vec![(2, 0), (3, 0)], RegionConstraint::Product(12)),
+            ]
+        );
+        assert_eq!(
+            options.clones,
+            vec![CloneRegion(vec![(0, 0), (1, 0)], vec![(4, 4), (5, 4)])]
+        );
+        assert_eq!(options.parity, vec![(8, 8, Parity::Even)]);
+        assert_eq!(
+            options.edge_clues,
+            vec![EdgeClue::new((4, 0), (5, 0), EdgeRelation::Ratio(2))]
+        );
+        assert!(options.disjoint_groups);
+        assert!(options.thermometers.is_empty());
+    }
+
+    #[test]
+    fn test_from_variant_errors() {
+        let error = |s: &str| SolveOptions::from_variant(s).unwrap_err();
+        assert_eq!(error("\nsquare 0,0"), "line 2: unknown constraint 'square'");
+        assert_eq!(
+            error("killer 3 0,0 1;0"),
+            "line 1: invalid cell '1;0', expected x,y"
+        );
+        assert_eq!(error("cage sum"), "line 1: missing sum");
+        assert_eq!(
+            error("edge sum 10 0,0"),
+            "line 1: edge has 1 cells instead of 2"
+        );
+        assert_eq!(
+            error("clone 0,0 1,0"),
+            "line 1: clone needs two lists of cells separated by '/'"
+        );
+        assert_eq!(
+            error("region 0,0 1,1"),
+            "invalid constraint: region has 2 cells instead of 9"
+        );
+    }
+}
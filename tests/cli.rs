@@ -0,0 +1,123 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+const PUZZLE: &str =
+    "53  7    6  195    98    6 8   6   34  8 3  17   2   6 6    28    419  5    8  79";
+const SOLUTION: &str =
+    "534678912672195348198342567859761423426853791713924856961537284287419635345286179";
+
+/// Runs the binary with the given arguments, feeding it `input` on stdin.
+fn run(args: &[&str], input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sudosat"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    writeln!(child.stdin.take().unwrap(), "{input}").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_format_line() {
+    assert_eq!(run(&["--format", "line"], PUZZLE), format!("{SOLUTION}\n"));
+}
+
+#[test]
+fn test_format_pretty() {
+    let output = run(&["--format", "pretty"], PUZZLE);
+    assert!(output.contains("solution ("));
+    assert!(output.contains("5 3 4 | 6 7 8 | 9 1 2"));
+    assert!(run(&[], PUZZLE).contains("5 3 4 | 6 7 8 | 9 1 2"));
+}
+
+#[test]
+fn test_format_svg() {
+    let output = run(&["--format", "svg"], PUZZLE);
+    assert!(output.starts_with("<svg"));
+    assert_eq!(output.matches("<text").count(), 81);
+}
+
+#[test]
+fn test_format_json() {
+    let output = run(&["--format", "json"], PUZZLE);
+    assert!(output.starts_with("[[5,3,4,6,7,8,9,1,2],[6,7,2,"));
+    assert!(!output.contains("null"));
+}
+
+#[test]
+fn test_batch() {
+    let input = format!("{PUZZLE}\n{}", " ".repeat(80) + "x");
+    let output = run(&["--batch"], &input);
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], SOLUTION);
+    assert!(lines[1].starts_with("error: "));
+}
+
+/// The progress bar is only drawn on a terminal, so the output is the same as without it.
+#[cfg(feature = "progress")]
+#[test]
+fn test_batch_progress() {
+    let output = run(&["--batch"], &format!("{PUZZLE}\n{PUZZLE}"));
+    assert_eq!(output, format!("{SOLUTION}\n{SOLUTION}\n"));
+}
+
+#[test]
+fn test_bench() {
+    let file = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/bench.txt");
+    let output = run(&["bench", "--file", file], "");
+    assert!(output.starts_with("puzzles: 4 (0 without a solution or malformed)\n"));
+    assert!(output.contains("puzzles/sec: "));
+
+    let output = run(&["bench", "--file", file, "--count", "2"], "");
+    assert!(output.starts_with("puzzles: 2 "));
+    assert!(output.contains("per puzzle: "));
+}
+
+#[test]
+fn test_check_unique() {
+    let output = run(&["--format", "line", "--check-unique"], PUZZLE);
+    assert_eq!(output, format!("{SOLUTION}\nThe solution is unique\n"));
+
+    // The 6s and 7s in the first two cells of the second stack in rows 1 and 4 can be swapped
+    let mut ambiguous = SOLUTION.to_string();
+    for i in [3, 4, 30, 31] {
+        ambiguous.replace_range(i..i + 1, " ");
+    }
+    let output = run(&["--format", "line", "--check-unique"], &ambiguous);
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[1], "The solution is not unique, another solution is:");
+    assert_ne!(lines[0], lines[2]);
+    for solution in [lines[0], lines[2]] {
+        assert_eq!(solution.len(), 81);
+        assert!(solution
+            .chars()
+            .zip(ambiguous.chars())
+            .all(|(s, a)| a == ' ' || s == a));
+    }
+}
+
+#[test]
+fn test_no_solution() {
+    // Only an unsatisfiable puzzle is reported as having no solution, on stdout
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sudosat"))
+        .args(["--format", "line", "--check-unique"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    writeln!(child.stdin.take().unwrap(), "11{}", " ".repeat(79)).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .starts_with("No solution!"));
+    assert!(output.stderr.is_empty());
+}
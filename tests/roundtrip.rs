@@ -0,0 +1,120 @@
+//! Every format a grid can be written in reads back as the same grid. The grids are generated
+//! from fixed seeds, so a failure can be reproduced from the seed in the message.
+use std::{io::Cursor, str::FromStr};
+
+use sudosat::{var_for, Alphabet, DisplayStyle, Grid};
+
+const CASES: u64 = 200;
+
+/// Number of solutions the grids are cut from; generating them is the slow part.
+const SOLUTIONS: u64 = 10;
+
+/// A puzzle cut from `solution`, keeping each cell with a probability that depends on the seed,
+/// from empty to completely filled in.
+fn random_grid(solution: &Grid, seed: u64) -> Grid {
+    let keep_per_mille = seed * 997 % 1001;
+    let mut state = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+    let keep = (0..81)
+        .filter(|_| {
+            // xorshift64
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % 1000 < keep_per_mille
+        })
+        .map(|i| (i % 9, i / 9))
+        .collect::<Vec<_>>();
+    solution.keep_only(&keep)
+}
+
+/// The filled in cells as the literals of a model, the input of [`Grid::from_dimacs_model`].
+fn dimacs_model(grid: &Grid) -> String {
+    let mut model = String::from("v");
+    for (x, y, d) in grid.filled_cells() {
+        model.push_str(&format!(
+            " {}",
+            var_for(x, y, d as usize).positive().to_dimacs()
+        ));
+    }
+    model + " 0"
+}
+
+#[test]
+fn test_roundtrip() {
+    let alphabet = Alphabet::new("ABCDEFGHI").unwrap();
+    let dots = DisplayStyle {
+        empty: '.',
+        ..DisplayStyle::default()
+    };
+    let plain = DisplayStyle {
+        separators: false,
+        block_lines: false,
+        ..DisplayStyle::default()
+    };
+
+    let solutions = (0..SOLUTIONS)
+        .map(Grid::generate_filled)
+        .collect::<Vec<_>>();
+    for seed in 0..CASES {
+        let grid = random_grid(&solutions[(seed % SOLUTIONS) as usize], seed);
+        let expected = Ok(grid.clone());
+        assert_eq!(
+            Grid::from_str(&grid.to_line()),
+            expected,
+            "line, seed {seed}"
+        );
+        assert_eq!(
+            Grid::from_str(&grid.format_with(&plain)),
+            expected,
+            "rows, seed {seed}"
+        );
+        assert_eq!(
+            Grid::from_reader(Cursor::new(grid.format_with(&dots))),
+            expected,
+            "reader, seed {seed}"
+        );
+        assert_eq!(
+            Grid::from_reader(grid.to_line().as_bytes()),
+            expected,
+            "line reader, seed {seed}"
+        );
+        let letters = grid
+            .to_line()
+            .chars()
+            .map(|c| match c.to_digit(10) {
+                Some(d) => alphabet.symbol(d as u8 - 1),
+                None => c,
+            })
+            .collect::<String>();
+        assert_eq!(
+            Grid::from_str_with_alphabet(&letters, &alphabet),
+            expected,
+            "alphabet, seed {seed}"
+        );
+        assert_eq!(
+            Grid::from_array(&grid.to_array()),
+            expected,
+            "array, seed {seed}"
+        );
+        assert_eq!(
+            Grid::from_coords(&grid.to_coords()),
+            expected,
+            "coords, seed {seed}"
+        );
+        assert_eq!(
+            Grid::from_bytes(&grid.to_bytes()),
+            expected,
+            "bytes, seed {seed}"
+        );
+        assert_eq!(
+            Grid::from_base64(&grid.to_base64()),
+            expected,
+            "base64, seed {seed}"
+        );
+        assert_eq!(
+            Grid::from_dimacs_model(&dimacs_model(&grid)),
+            expected,
+            "dimacs model, seed {seed}"
+        );
+    }
+}
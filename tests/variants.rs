@@ -0,0 +1,79 @@
+use sudosat::{ExtraRegion, Grid, Parity, SolveError, SolveOptions};
+
+/// Returns whether the cells contain nine different digits.
+fn all_different(grid: &Grid, cells: &[(usize, usize)]) -> bool {
+    let mut seen = [false; 9];
+    cells.iter().all(|&(x, y)| {
+        let d = grid.get(x, y).unwrap() as usize;
+        !std::mem::replace(&mut seen[d], true)
+    })
+}
+
+#[test]
+fn test_combined_variants() {
+    let diagonal = (0..9).map(|i| (i, i)).collect::<Vec<_>>();
+    let even_diagonal = diagonal
+        .iter()
+        .map(|&(x, y)| (x, y, Parity::Even))
+        .collect::<Vec<_>>();
+
+    // The diagonal holds nine different digits, but only four of them are even. Each constraint
+    // on its own can be satisfied, together they cannot.
+    let diagonal_only = SolveOptions {
+        extra_regions: vec![ExtraRegion::main_diagonal()],
+        ..SolveOptions::default()
+    };
+    let parity_only = SolveOptions {
+        parity: even_diagonal.clone(),
+        ..SolveOptions::default()
+    };
+    let both = SolveOptions {
+        extra_regions: vec![ExtraRegion::main_diagonal()],
+        parity: even_diagonal,
+        ..SolveOptions::default()
+    };
+    assert!(Grid::empty().solve_with(&diagonal_only).is_ok());
+    assert!(Grid::empty().solve_with(&parity_only).is_ok());
+    assert_eq!(
+        Grid::empty().solve_with(&both).err(),
+        Some(SolveError::Unsatisfiable)
+    );
+
+    // Compatible variants all hold in the solution
+    let anti_diagonal = (0..9).map(|i| (8 - i, i)).collect::<Vec<_>>();
+    let options = SolveOptions {
+        extra_regions: vec![ExtraRegion::main_diagonal(), ExtraRegion::anti_diagonal()],
+        disjoint_groups: true,
+        parity: vec![(0, 0, Parity::Even), (4, 4, Parity::Odd)],
+        verify: true,
+        ..SolveOptions::default()
+    };
+    let (solution, _) = Grid::empty().solve_with(&options).unwrap();
+    assert!(sudosat::is_valid_solution(&solution));
+    assert!(all_different(&solution, &diagonal));
+    assert!(all_different(&solution, &anti_diagonal));
+    for position in 0..9 {
+        let group = (0..9)
+            .map(|b| (b % 3 * 3 + position % 3, b / 3 * 3 + position / 3))
+            .collect::<Vec<_>>();
+        assert!(all_different(&solution, &group));
+    }
+    assert_eq!(solution.get(0, 0).unwrap() % 2, 1);
+    assert_eq!(solution.get(4, 4).unwrap() % 2, 0);
+}
+
+#[test]
+fn test_variant_file() {
+    let description = include_str!("fixtures/variant.txt");
+    let options = SolveOptions {
+        verify: true,
+        ..SolveOptions::from_variant(description).unwrap()
+    };
+    let (solution, _) = Grid::empty().solve_with(&options).unwrap();
+
+    let diagonal = (0..9).map(|i| (i, i)).collect::<Vec<_>>();
+    assert!(all_different(&solution, &diagonal));
+    assert_eq!(solution.row(4), std::array::from_fn(|x| Some(x as u8)));
+    let cage = solution.get(0, 0).unwrap() + solution.get(1, 0).unwrap() + 2;
+    assert_eq!(cage, 17);
+}